@@ -0,0 +1,3 @@
+ALTER TABLE links
+    ADD COLUMN expires_at TIMESTAMPTZ,
+    ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
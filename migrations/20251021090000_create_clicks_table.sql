@@ -0,0 +1,7 @@
+CREATE TABLE clicks (
+    id BIGSERIAL PRIMARY KEY,
+    link_id UUID NOT NULL REFERENCES links (id) ON DELETE CASCADE,
+    clicked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX clicks_link_id_idx ON clicks (link_id);
@@ -0,0 +1,2 @@
+ALTER TABLE links
+    ADD COLUMN status TEXT NOT NULL DEFAULT 'active';
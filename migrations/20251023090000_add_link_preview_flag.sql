@@ -0,0 +1,2 @@
+ALTER TABLE links
+    ADD COLUMN always_preview BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,5 @@
+ALTER TABLE links
+    ADD COLUMN owner TEXT;
+
+CREATE INDEX links_owner_long_url_idx ON links (owner, long_url)
+    WHERE owner IS NOT NULL;
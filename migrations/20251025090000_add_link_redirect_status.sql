@@ -0,0 +1,3 @@
+ALTER TABLE links
+    ADD COLUMN redirect_status SMALLINT
+        CHECK (redirect_status IN (301, 302, 307, 308));
@@ -0,0 +1,2 @@
+ALTER TABLE links
+    ADD COLUMN passthrough BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,9 @@
+ALTER TABLE links
+    ADD COLUMN utm_source TEXT,
+    ADD COLUMN utm_medium TEXT,
+    ADD COLUMN utm_campaign TEXT,
+    ADD COLUMN utm_term TEXT,
+    ADD COLUMN utm_content TEXT;
+
+CREATE INDEX links_utm_campaign_idx ON links (utm_campaign)
+    WHERE utm_campaign IS NOT NULL;
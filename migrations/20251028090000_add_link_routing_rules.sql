@@ -0,0 +1,2 @@
+ALTER TABLE links
+    ADD COLUMN routing_rules JSONB NOT NULL DEFAULT '[]';
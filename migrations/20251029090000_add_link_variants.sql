@@ -0,0 +1,5 @@
+ALTER TABLE links
+    ADD COLUMN variants JSONB NOT NULL DEFAULT '[]';
+
+ALTER TABLE clicks
+    ADD COLUMN variant TEXT;
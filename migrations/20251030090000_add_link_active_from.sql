@@ -0,0 +1,2 @@
+ALTER TABLE links
+    ADD COLUMN active_from TIMESTAMPTZ;
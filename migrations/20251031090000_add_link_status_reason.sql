@@ -0,0 +1,2 @@
+ALTER TABLE links
+    ADD COLUMN status_reason TEXT;
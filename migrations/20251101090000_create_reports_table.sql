@@ -0,0 +1,12 @@
+CREATE TABLE reports (
+    id UUID PRIMARY KEY,
+    link_id UUID NOT NULL REFERENCES links (id) ON DELETE CASCADE,
+    reason TEXT NOT NULL,
+    details TEXT,
+    reporter_hash TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    state TEXT NOT NULL DEFAULT 'open',
+    UNIQUE (link_id, reporter_hash)
+);
+
+CREATE INDEX reports_state_created_at_idx ON reports (state, created_at);
@@ -0,0 +1,144 @@
+use std::net::IpAddr;
+
+use crate::domain::{
+    ports::GeoLocator,
+    routing::{Audience, DeviceKind, OsKind},
+};
+
+// The parts of an incoming request that routing rules can look at.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestContext {
+    pub user_agent: Option<String>,
+    pub accept_language: Option<String>,
+    pub client_ip: Option<IpAddr>,
+    // Variant remembered from an earlier visit to the same link.
+    pub sticky_variant: Option<String>,
+}
+
+impl RequestContext {
+    pub fn audience(&self, locator: Option<&dyn GeoLocator>) -> Audience {
+        let (device, os) = match self.user_agent.as_deref() {
+            Some(user_agent) => {
+                let (device, os) = classify_user_agent(user_agent);
+                (Some(device), Some(os))
+            }
+            None => (None, None),
+        };
+
+        Audience {
+            device,
+            os,
+            language: self.accept_language.as_deref().and_then(preferred_language),
+            country: locator
+                .zip(self.client_ip)
+                .and_then(|(locator, ip)| locator.country(ip))
+                .map(|country| country.to_ascii_uppercase()),
+        }
+    }
+}
+
+// Deliberately coarse: rules only need to tell phones from tablets from
+// desktops and which platform store to send someone to.
+pub fn classify_user_agent(user_agent: &str) -> (DeviceKind, OsKind) {
+    let ua = user_agent.to_ascii_lowercase();
+    let has = |needle: &str| ua.contains(needle);
+
+    let os = if has("iphone") || has("ipad") || has("ipod") {
+        OsKind::Ios
+    } else if has("android") {
+        OsKind::Android
+    } else if has("windows") {
+        OsKind::Windows
+    } else if has("macintosh") || has("mac os x") {
+        OsKind::MacOs
+    } else if has("linux") || has("x11") || has("cros") {
+        OsKind::Linux
+    } else {
+        OsKind::Other
+    };
+
+    let device = if ["bot", "crawler", "spider", "slurp"]
+        .iter()
+        .any(|needle| has(needle))
+    {
+        DeviceKind::Bot
+    } else if has("ipad") || has("tablet") || (os == OsKind::Android && !has("mobile")) {
+        DeviceKind::Tablet
+    } else if has("mobi") || has("iphone") || has("ipod") || os == OsKind::Android {
+        DeviceKind::Mobile
+    } else {
+        DeviceKind::Desktop
+    };
+
+    (device, os)
+}
+
+// Highest-weighted tag from an `Accept-Language` header; ties keep header
+// order and `*` is ignored.
+pub fn preferred_language(header: &str) -> Option<String> {
+    header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let tag = parts.next()?.trim().to_ascii_lowercase();
+
+            let weight = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+
+            (!tag.is_empty() && tag != "*" && weight > 0.0).then_some((tag, weight))
+        })
+        .fold(
+            None,
+            |best: Option<(String, f32)>, (tag, weight)| match best {
+                Some((_, best_weight)) if best_weight >= weight => best,
+                _ => Some((tag, weight)),
+            },
+        )
+        .map(|(tag, _)| tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_agents_are_classified() {
+        let cases = [
+            (
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) Mobile/15E148",
+                (DeviceKind::Mobile, OsKind::Ios),
+            ),
+            (
+                "Mozilla/5.0 (Linux; Android 14; Pixel 8) Chrome/120.0 Mobile Safari/537.36",
+                (DeviceKind::Mobile, OsKind::Android),
+            ),
+            (
+                "Mozilla/5.0 (Linux; Android 13; SM-X700) Chrome/120.0 Safari/537.36",
+                (DeviceKind::Tablet, OsKind::Android),
+            ),
+            (
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) Chrome/120.0 Safari/537.36",
+                (DeviceKind::Desktop, OsKind::Windows),
+            ),
+            (
+                "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+                (DeviceKind::Bot, OsKind::Other),
+            ),
+        ];
+
+        for (user_agent, expected) in cases {
+            assert_eq!(classify_user_agent(user_agent), expected, "{user_agent}");
+        }
+    }
+
+    #[test]
+    fn preferred_language_follows_weights() {
+        assert_eq!(
+            preferred_language("de;q=0.7, fr-CH, en;q=0.9"),
+            Some("fr-ch".to_string())
+        );
+        assert_eq!(preferred_language("*, es;q=0.5"), Some("es".to_string()));
+        assert_eq!(preferred_language("en;q=0"), None);
+    }
+}
@@ -1,5 +1,105 @@
-use std::net::{IpAddr, ToSocketAddrs};
-use url::Url as ExternalUrl;
+use chrono::{DateTime, Utc};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use url::{Host, Url as ExternalUrl};
+
+use crate::application::policy::DomainPolicy;
+use crate::domain::{
+    click::ClickStats,
+    errors::UrlError,
+    link::{Link, RedirectKind, UserUrl, Utm, MAX_URL_LENGTH},
+    ports::HostResolver,
+    routing::RuleConditions,
+};
+
+// How a visit to a short link is answered. `Auto` previews only links
+// flagged for it; `Follow` is the visitor continuing from a preview.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisitMode {
+    Preview,
+    Auto,
+    Follow,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Visit {
+    // Nothing was counted; the visitor has not left yet.
+    Preview(Link, ClickStats),
+    Redirect(RedirectTarget),
+}
+
+// Where a visitor ends up: the link itself plus the destination after any
+// passthrough path and query have been applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RedirectTarget {
+    pub link: Link,
+    pub destination: UserUrl,
+    // Set when an A/B split picked the destination.
+    pub variant: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewRoutingRule {
+    pub conditions: RuleConditions,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewVariant {
+    pub name: String,
+    pub url: String,
+    pub weight: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewReport {
+    pub reason: String,
+    pub details: Option<String>,
+    // Only a salted hash of it is stored.
+    pub reporter_ip: Option<IpAddr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportOutcome {
+    Recorded,
+    // This reporter had already reported the link; nothing was stored.
+    Duplicate,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NewLink {
+    pub url: String,
+    pub alias: Option<String>,
+    // Scheduled launch; the link answers "coming soon" until then.
+    pub active_from: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+    pub always_preview: bool,
+    // `None` leaves the choice to the server default.
+    pub redirect_kind: Option<RedirectKind>,
+    pub passthrough: bool,
+    pub utm: Utm,
+    // Tried in order before falling back to `url`.
+    pub routing_rules: Vec<NewRoutingRule>,
+    pub variants: Vec<NewVariant>,
+    // The API key holder the link belongs to, if any.
+    pub owner: Option<String>,
+    // Hand back the owner's existing link for the same destination instead
+    // of minting a new code.
+    pub reuse_existing: bool,
+}
+
+impl NewLink {
+    pub fn from_url(url: String) -> Self {
+        Self {
+            url,
+            ..Self::default()
+        }
+    }
+}
+
+// OWASP A03 Injection: `javascript:`, `data:` and `file:` destinations would
+// run in, or read from, the visitor's browser.
+const ALLOWED_SCHEMES: [&str; 2] = ["http", "https"];
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Url {
@@ -7,35 +107,62 @@ pub struct Url {
 }
 
 impl Url {
-    pub fn new(value: &str) -> Result<Self, String> {
+    pub async fn new(
+        value: &str,
+        resolver: &dyn HostResolver,
+        policy: &DomainPolicy,
+    ) -> Result<Self, UrlError> {
         let value_trim = value.trim();
 
         if value_trim.is_empty() {
-            return Err("Empty url".to_string());
+            return Err(UrlError::Empty);
+        }
+
+        if value_trim.len() > MAX_URL_LENGTH {
+            return Err(UrlError::TooLong {
+                max: MAX_URL_LENGTH,
+            });
         }
 
         // OWASP 08 Data integrity faliure
-        let parsed =
-            ExternalUrl::parse(value_trim).map_err(|_| "Invalid URL format".to_string())?;
+        let parsed = ExternalUrl::parse(value_trim).map_err(|_| UrlError::Unparsable)?;
+
+        if !ALLOWED_SCHEMES.contains(&parsed.scheme()) {
+            return Err(UrlError::BadScheme(parsed.scheme().to_string()));
+        }
+
+        let host = parsed.host().ok_or(UrlError::MissingHost)?;
+        let host_name = host.to_string();
 
-        let domain = parsed
-            .host_str()
-            .ok_or_else(|| "Missing domain in URL".to_string())?;
+        // Name-based rules are checked first so denied hosts are never looked up.
+        if policy.denies_host(&host_name) {
+            return Err(UrlError::BlockedDomain);
+        }
 
         // OWASP 10 Server Side Request  Forgery
-        let socket_addrs = (domain, 25)
-            .to_socket_addrs()
-            .map_err(|_| "Cannot resolve domain")?;
+        let addresses = match host {
+            Host::Ipv4(ip) => vec![IpAddr::V4(ip)],
+            Host::Ipv6(ip) => vec![IpAddr::V6(ip)],
+            Host::Domain(domain) => resolver
+                .resolve(domain)
+                .await
+                .map_err(|_| UrlError::Unresolvable)?,
+        };
+
+        if addresses.is_empty() {
+            return Err(UrlError::Unresolvable);
+        }
+
+        if addresses.iter().any(is_reserved_ip) {
+            return Err(UrlError::PrivateAddress);
+        }
 
-        if socket_addrs
-            .map(|addr| addr.ip())
-            .any(|ip| is_private_ip(&ip))
-        {
-            return Err("Domain resolves to a private Ip".to_string());
+        if !policy.permits(&host_name, &addresses) {
+            return Err(UrlError::BlockedDomain);
         }
 
         Ok(Self {
-            raw: value.to_string(),
+            raw: value_trim.to_string(),
         })
     }
 
@@ -44,10 +171,283 @@ impl Url {
     }
 }
 
-// OWASP 10 Server Side Request Forgery
-fn is_private_ip(ip: &IpAddr) -> bool {
+// OWASP 10 Server Side Request Forgery: anything that is not globally
+// routable unicast address space is refused.
+pub fn is_reserved_ip(ip: &IpAddr) -> bool {
     match ip {
-        IpAddr::V4(ipv4) => ipv4.is_private() || ipv4.is_loopback(),
-        IpAddr::V6(ipv6) => ipv6.is_loopback() || ipv6.is_unique_local(),
+        IpAddr::V4(ipv4) => is_reserved_ipv4(ipv4),
+        IpAddr::V6(ipv6) => is_reserved_ipv6(ipv6),
+    }
+}
+
+fn is_reserved_ipv4(ip: &Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+
+    ip.is_unspecified()
+        || ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_multicast()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        // 0.0.0.0/8 "this network"
+        || a == 0
+        // 100.64.0.0/10 carrier-grade NAT
+        || (a == 100 && (b & 0b1100_0000) == 64)
+        // 192.0.0.0/24 IETF protocol assignments
+        || (a == 192 && b == 0 && c == 0)
+        // 198.18.0.0/15 benchmarking
+        || (a == 198 && (b & 0xfe) == 18)
+        // 240.0.0.0/4 reserved for future use
+        || a >= 240
+}
+
+fn is_reserved_ipv6(ip: &Ipv6Addr) -> bool {
+    let segments = ip.segments();
+
+    // Addresses that embed an IPv4 address are judged by that address:
+    // ::ffff:0:0/96 (mapped), 64:ff9b::/96 (NAT64) and 2002::/16 (6to4).
+    if let Some(ipv4) = ip.to_ipv4_mapped() {
+        return is_reserved_ipv4(&ipv4);
+    }
+
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        let [.., c, d] = segments;
+        return is_reserved_ipv4(&embedded_ipv4(c, d));
+    }
+
+    if segments[0] == 0x2002 {
+        return is_reserved_ipv4(&embedded_ipv4(segments[1], segments[2]));
+    }
+
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || ip.is_unique_local()
+        || ip.is_unicast_link_local()
+        // ::/96 deprecated IPv4-compatible addresses
+        || segments[..6] == [0, 0, 0, 0, 0, 0]
+        // 2001:db8::/32 documentation
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8)
+        // 100::/64 discard-only
+        || segments[..4] == [0x100, 0, 0, 0]
+}
+
+fn embedded_ipv4(high: u16, low: u16) -> Ipv4Addr {
+    let [a, b] = high.to_be_bytes();
+    let [c, d] = low.to_be_bytes();
+
+    Ipv4Addr::new(a, b, c, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::io;
+
+    #[derive(Debug, Default)]
+    struct StubResolver {
+        hosts: HashMap<&'static str, Vec<IpAddr>>,
+    }
+
+    impl StubResolver {
+        fn with(mut self, host: &'static str, ips: &[&str]) -> Self {
+            let ips = ips.iter().map(|ip| ip.parse().unwrap()).collect();
+            self.hosts.insert(host, ips);
+            self
+        }
+    }
+
+    #[async_trait]
+    impl HostResolver for StubResolver {
+        async fn resolve(&self, host: &str) -> io::Result<Vec<IpAddr>> {
+            match host {
+                "slow.example" => Err(io::ErrorKind::TimedOut.into()),
+                _ => self
+                    .hosts
+                    .get(host)
+                    .cloned()
+                    .ok_or_else(|| io::ErrorKind::NotFound.into()),
+            }
+        }
+    }
+
+    fn resolver() -> StubResolver {
+        StubResolver::default()
+            .with("public.example", &["93.184.216.34", "2606:2800:220:1::"])
+            .with("internal.example", &["10.0.0.5"])
+            .with("mixed.example", &["93.184.216.34", "127.0.0.1"])
+            .with("metadata.example", &["169.254.169.254"])
+            .with("empty.example", &[])
+    }
+
+    #[tokio::test]
+    async fn accepts_public_destinations() {
+        let resolver = resolver();
+
+        for url in [
+            "https://public.example/path?q=1",
+            "http://public.example",
+            "https://93.184.216.34/",
+            "https://[2606:2800:220:1::]/",
+        ] {
+            assert!(
+                Url::new(url, &resolver, &DomainPolicy::default())
+                    .await
+                    .is_ok(),
+                "{url}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_disallowed_schemes() {
+        let resolver = resolver();
+
+        for url in [
+            "javascript:alert(1)",
+            "data:text/html,<script>alert(1)</script>",
+            "file:///etc/passwd",
+            "ftp://public.example/file",
+        ] {
+            let error = Url::new(url, &resolver, &DomainPolicy::default())
+                .await
+                .unwrap_err();
+            assert!(matches!(error, UrlError::BadScheme(_)), "{url}: {error:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_hosts_resolving_to_reserved_space() {
+        let resolver = resolver();
+
+        for url in [
+            "https://internal.example",
+            "https://mixed.example",
+            "https://metadata.example/latest/meta-data",
+            "http://127.0.0.1:8080",
+            "http://[::ffff:10.0.0.1]/",
+            "http://[fe80::1]/",
+        ] {
+            let error = Url::new(url, &resolver, &DomainPolicy::default())
+                .await
+                .unwrap_err();
+            assert_eq!(error, UrlError::PrivateAddress, "{url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_unresolvable_hosts() {
+        let resolver = resolver();
+
+        for url in [
+            "https://unknown.example",
+            "https://slow.example",
+            "https://empty.example",
+        ] {
+            let error = Url::new(url, &resolver, &DomainPolicy::default())
+                .await
+                .unwrap_err();
+            assert_eq!(error, UrlError::Unresolvable, "{url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_input() {
+        let resolver = resolver();
+        let too_long = format!("https://public.example/{}", "a".repeat(MAX_URL_LENGTH));
+
+        let cases = [
+            ("   ", UrlError::Empty),
+            ("not a url", UrlError::Unparsable),
+            ("https://", UrlError::Unparsable),
+            (
+                too_long.as_str(),
+                UrlError::TooLong {
+                    max: MAX_URL_LENGTH,
+                },
+            ),
+        ];
+
+        for (url, expected) in cases {
+            let error = Url::new(url, &resolver, &DomainPolicy::default())
+                .await
+                .unwrap_err();
+            assert_eq!(error, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn applies_the_domain_policy() {
+        let resolver = resolver();
+        let policy = DomainPolicy::parse("deny *.example\nallow public.example").unwrap();
+
+        let error = Url::new("https://public.example", &resolver, &policy)
+            .await
+            .unwrap_err();
+        assert_eq!(error, UrlError::BlockedDomain);
+
+        // Denied names are refused before any lookup happens.
+        let error = Url::new("https://slow.example", &resolver, &policy)
+            .await
+            .unwrap_err();
+        assert_eq!(error, UrlError::BlockedDomain);
+    }
+
+    #[test]
+    fn classifies_reserved_ranges() {
+        for ip in [
+            "0.0.0.0",
+            "0.1.2.3",
+            "10.1.2.3",
+            "100.64.0.1",
+            "100.127.255.254",
+            "127.0.0.1",
+            "169.254.169.254",
+            "172.16.0.1",
+            "192.0.0.8",
+            "192.0.2.1",
+            "192.168.1.1",
+            "198.18.0.1",
+            "198.51.100.7",
+            "203.0.113.9",
+            "224.0.0.1",
+            "240.0.0.1",
+            "255.255.255.255",
+            "::",
+            "::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.1.1",
+            "64:ff9b::a00:1",
+            "2002:c0a8:101::1",
+            "fc00::1",
+            "fd12:3456::1",
+            "fe80::1",
+            "ff02::1",
+            "2001:db8::1",
+        ] {
+            let ip: IpAddr = ip.parse().unwrap();
+            assert!(is_reserved_ip(&ip), "{ip} should be reserved");
+        }
+    }
+
+    #[test]
+    fn classifies_public_ranges() {
+        for ip in [
+            "1.1.1.1",
+            "8.8.8.8",
+            "93.184.216.34",
+            "100.63.255.255",
+            "100.128.0.1",
+            "172.32.0.1",
+            "::ffff:8.8.8.8",
+            "64:ff9b::808:808",
+            "2606:4700:4700::1111",
+        ] {
+            let ip: IpAddr = ip.parse().unwrap();
+            assert!(!is_reserved_ip(&ip), "{ip} should be public");
+        }
     }
 }
@@ -1,4 +1,7 @@
+pub mod audience;
 pub mod command;
+pub mod policy;
 pub mod service;
+pub mod threat;
+pub mod transfer;
 pub mod usecase;
-
@@ -0,0 +1,202 @@
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostRule {
+    // `example.com`
+    Exact(String),
+    // `*.example.com` matches every subdomain, but not `example.com` itself.
+    Subdomains(String),
+    // `203.0.113.0/24`, or a bare address for a single host.
+    Network(IpNet),
+}
+
+impl HostRule {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let rule = raw.trim().trim_end_matches('.').to_ascii_lowercase();
+
+        if let Ok(net) = rule.parse::<IpNet>() {
+            return Ok(HostRule::Network(net));
+        }
+
+        if let Ok(ip) = rule.parse::<IpAddr>() {
+            return Ok(HostRule::Network(IpNet::from(ip)));
+        }
+
+        let (wildcard, host) = match rule.strip_prefix("*.") {
+            Some(host) => (true, host.to_string()),
+            None => (false, rule),
+        };
+
+        let valid = !host.is_empty()
+            && host.split('.').all(|label| {
+                !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+
+        if !valid {
+            return Err(format!("'{raw}' is not a host, wildcard or CIDR"));
+        }
+
+        Ok(if wildcard {
+            HostRule::Subdomains(host)
+        } else {
+            HostRule::Exact(host)
+        })
+    }
+
+    pub fn matches_host(&self, host: &str) -> bool {
+        match self {
+            HostRule::Exact(rule) => host == rule,
+            HostRule::Subdomains(rule) => host
+                .strip_suffix(rule.as_str())
+                .is_some_and(|prefix| prefix.ends_with('.')),
+            HostRule::Network(net) => host
+                .trim_matches(|c| c == '[' || c == ']')
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| net.contains(&ip)),
+        }
+    }
+
+    pub fn matches_ip(&self, ip: &IpAddr) -> bool {
+        match self {
+            HostRule::Network(net) => net.contains(ip),
+            _ => false,
+        }
+    }
+}
+
+// Deny rules always win. When any allow rule exists, a destination must
+// also match one of them by name or have every address inside an allowed
+// network.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DomainPolicy {
+    allow: Vec<HostRule>,
+    deny: Vec<HostRule>,
+}
+
+impl DomainPolicy {
+    pub fn new(allow: Vec<HostRule>, deny: Vec<HostRule>) -> Self {
+        Self { allow, deny }
+    }
+
+    // One rule per line: `allow <rule>` or `deny <rule>`; `#` starts a comment.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut policy = Self::default();
+
+        for (index, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let (action, rule) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| format!("line {}: expected '<allow|deny> <rule>'", index + 1))?;
+
+            let rule = HostRule::parse(rule).map_err(|e| format!("line {}: {e}", index + 1))?;
+
+            match action {
+                "allow" => policy.allow.push(rule),
+                "deny" => policy.deny.push(rule),
+                other => return Err(format!("line {}: unknown action '{other}'", index + 1)),
+            }
+        }
+
+        Ok(policy)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    // Decided from the name alone, so it is cheap enough for every redirect.
+    pub fn denies_host(&self, host: &str) -> bool {
+        let host = normalize(host);
+        self.deny.iter().any(|rule| rule.matches_host(&host))
+    }
+
+    pub fn permits(&self, host: &str, addresses: &[IpAddr]) -> bool {
+        let host = normalize(host);
+
+        if self.denies_host(&host)
+            || addresses
+                .iter()
+                .any(|ip| self.deny.iter().any(|rule| rule.matches_ip(ip)))
+        {
+            return false;
+        }
+
+        if self.allow.is_empty() {
+            return true;
+        }
+
+        self.allow.iter().any(|rule| rule.matches_host(&host))
+            || (!addresses.is_empty()
+                && addresses
+                    .iter()
+                    .all(|ip| self.allow.iter().any(|rule| rule.matches_ip(ip))))
+    }
+}
+
+fn normalize(host: &str) -> String {
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(raw: &str) -> IpAddr {
+        raw.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_rule_kinds() {
+        let policy = DomainPolicy::parse(
+            "# comment\n\
+             deny phish.example\n\
+             deny *.evil.example   # trailing comment\n\
+             allow 203.0.113.0/24\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            policy,
+            DomainPolicy::new(
+                vec![HostRule::Network("203.0.113.0/24".parse().unwrap())],
+                vec![
+                    HostRule::Exact("phish.example".to_string()),
+                    HostRule::Subdomains("evil.example".to_string()),
+                ],
+            )
+        );
+
+        assert!(DomainPolicy::parse("block example.com").is_err());
+        assert!(DomainPolicy::parse("deny exa mple").is_err());
+    }
+
+    #[test]
+    fn deny_rules_match_hosts_subdomains_and_networks() {
+        let policy =
+            DomainPolicy::parse("deny phish.example\ndeny *.evil.example\ndeny 198.51.100.0/24")
+                .unwrap();
+
+        assert!(!policy.permits("PHISH.example.", &[]));
+        assert!(!policy.permits("login.evil.example", &[]));
+        assert!(policy.permits("evil.example", &[ip("93.184.216.34")]));
+        assert!(policy.permits("notevil.example", &[ip("93.184.216.34")]));
+        assert!(!policy.permits("fine.example", &[ip("198.51.100.7")]));
+        assert!(!policy.permits("198.51.100.7", &[ip("198.51.100.7")]));
+    }
+
+    #[test]
+    fn allow_rules_restrict_everything_else() {
+        let policy = DomainPolicy::parse("allow *.corp.example\nallow 203.0.113.0/24").unwrap();
+
+        assert!(policy.permits("wiki.corp.example", &[ip("93.184.216.34")]));
+        assert!(policy.permits("cdn.example", &[ip("203.0.113.5")]));
+        assert!(!policy.permits("cdn.example", &[ip("203.0.113.5"), ip("93.184.216.34")]));
+        assert!(!policy.permits("other.example", &[ip("93.184.216.34")]));
+    }
+}
@@ -1,17 +1,71 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+use std::time::{Duration as StdDuration, Instant};
+
+use chrono::{Duration, NaiveTime};
+use futures::{future::join_all, stream, StreamExt};
+use rand::Rng;
+use uuid::Uuid;
+
 use crate::application::{
-    command::Url,
+    audience::RequestContext,
+    command::{
+        NewLink, NewReport, NewRoutingRule, NewVariant, RedirectTarget, ReportOutcome, Url, Visit,
+        VisitMode,
+    },
+    policy::DomainPolicy,
+    threat::ThreatList,
+    transfer::{ImportConflict, ImportOutcome, LinkEntry},
     usecase::{LinkPersistenceService, LinkQueryService},
 };
 use crate::domain::{
-    errors::LinkError,
-    link::{CreatedAt, Link, LinkId, LinkKey, ShortUrl},
-    ports::{LinkPersistence, LinkQuery},
+    click::{CampaignStats, Click, ClickStats, DailyClicks},
+    errors::{LinkError, UrlError},
+    link::{
+        key_matches, ActiveFrom, CreatedAt, ExpiresAt, Link, LinkId, LinkKey, LinkStatus,
+        QueryConflict, ShortUrl, Tag, UserUrl,
+    },
+    ports::{BatchMode, GeoLocator, HostResolver, LinkPersistence, LinkQuery},
+    report::{reporter_hash, Report, ReportReason, ReportState},
+    routing::{check_variants, total_weight, RoutingRule, Variant, MAX_RULES_PER_LINK},
 };
 
-#[derive(Debug, Clone, PartialEq)]
+// Enough for a ticket reference and a sentence of context.
+const MAX_STATUS_REASON_LEN: usize = 500;
+
+// OWASP A07 Identification and Authentication Failures: delete keys are
+// short, so wrong guesses against one link are cut off for a while.
+const MAX_DELETE_FAILURES: u32 = 5;
+const DELETE_LOCKOUT: StdDuration = StdDuration::from_secs(15 * 60);
+
+// Rows of a batch validated at once. Each may resolve several host names,
+// so a full batch at once would flood the resolver.
+const BATCH_CONCURRENCY: usize = 8;
+
+// A lookup that found nothing is an answer; any other failure is not.
+fn exists(lookup: Result<Link, LinkError>) -> Result<bool, LinkError> {
+    match lookup {
+        Ok(_) => Ok(true),
+        Err(LinkError::NotFound) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct LinkService<P: LinkPersistence, Q: LinkQuery> {
     persistence_service: LinkPersistenceService<P>,
     query_service: LinkQueryService<Q>,
+    resolver: Arc<dyn HostResolver>,
+    domain_policy: Arc<RwLock<DomainPolicy>>,
+    threat_list: Arc<RwLock<ThreatList>>,
+    strip_tracking_params: bool,
+    query_conflict: QueryConflict,
+    geo_locator: Option<Arc<dyn GeoLocator>>,
+    report_salt: String,
+    // Open reports that take an active link offline; 0 never does.
+    report_threshold: i64,
+    // Wrong delete keys per link: how many, and when the first came in.
+    delete_failures: Arc<Mutex<HashMap<Uuid, (u32, Instant)>>>,
 }
 
 impl<P, Q> LinkService<P, Q>
@@ -19,74 +73,852 @@ where
     P: LinkPersistence + Send + Sync,
     Q: LinkQuery + Send + Sync,
 {
-    pub async fn new(persistence: LinkPersistenceService<P>, query: LinkQueryService<Q>) -> Self {
+    pub async fn new(
+        persistence: LinkPersistenceService<P>,
+        query: LinkQueryService<Q>,
+        resolver: Arc<dyn HostResolver>,
+    ) -> Self {
         Self {
             persistence_service: persistence,
             query_service: query,
+            resolver,
+            domain_policy: Arc::default(),
+            threat_list: Arc::default(),
+            strip_tracking_params: false,
+            query_conflict: QueryConflict::default(),
+            geo_locator: None,
+            report_salt: String::new(),
+            report_threshold: 0,
+            delete_failures: Arc::default(),
+        }
+    }
+
+    // Drop `utm_*`, `fbclid` and friends from destinations before storing.
+    pub fn with_strip_tracking_params(mut self, strip: bool) -> Self {
+        self.strip_tracking_params = strip;
+        self
+    }
+
+    // How passthrough links treat parameters the destination already sets.
+    pub fn with_query_conflict(mut self, conflict: QueryConflict) -> Self {
+        self.query_conflict = conflict;
+        self
+    }
+
+    // Without a locator, country conditions never match.
+    pub fn with_geo_locator(mut self, locator: Option<Arc<dyn GeoLocator>>) -> Self {
+        self.geo_locator = locator;
+        self
+    }
+
+    pub fn with_report_salt(mut self, salt: String) -> Self {
+        self.report_salt = salt;
+        self
+    }
+
+    pub fn with_report_threshold(mut self, threshold: i64) -> Self {
+        self.report_threshold = threshold;
+        self
+    }
+
+    // Swapped in place so operators can change the lists without a restart.
+    pub fn set_domain_policy(&self, policy: DomainPolicy) {
+        *self
+            .domain_policy
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = policy;
+    }
+
+    pub fn set_threat_list(&self, list: ThreatList) {
+        *self
+            .threat_list
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = list;
+    }
+
+    fn check_threats(&self, url: &str) -> Result<(), LinkError> {
+        let hit = self
+            .threat_list
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .check(url);
+
+        match hit {
+            Some(hit) => Err(LinkError::ThreatDetected(hit.threat_type)),
+            None => Ok(()),
+        }
+    }
+
+    // Imported links never pass through `build_link`, so every destination
+    // they carry gets the creation checks here.
+    async fn check_destinations(&self, link: &Link) -> Result<(), LinkError> {
+        let policy = self.domain_policy();
+
+        let destinations = std::iter::once(link.user_url())
+            .chain(link.routing_rules().iter().map(RoutingRule::destination))
+            .chain(link.variants().iter().map(Variant::destination));
+
+        for destination in destinations {
+            Url::new(destination.as_str(), self.resolver.as_ref(), &policy).await?;
+            self.check_threats(destination.as_str())?;
         }
+
+        Ok(())
+    }
+
+    // Name-based deny rules only, so it is cheap enough for every redirect.
+    fn denies_destination(&self, url: &str) -> bool {
+        url::Url::parse(url)
+            .ok()
+            .and_then(|parsed| parsed.host_str().map(str::to_string))
+            .is_some_and(|host| self.domain_policy().denies_host(&host))
+    }
+
+    fn domain_policy(&self) -> DomainPolicy {
+        self.domain_policy
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
     }
 
     pub async fn create(&self, raw_user_url: String) -> Result<LinkId, LinkError> {
+        let link = self.create_link(NewLink::from_url(raw_user_url)).await?;
+
+        Ok(link.id().clone())
+    }
+
+    pub async fn create_link(&self, request: NewLink) -> Result<Link, LinkError> {
+        self.create_or_reuse(request)
+            .await
+            .map(|(link, _reused)| link)
+    }
+
+    // Reuse only applies to owned links without a custom alias: an alias is
+    // an explicit request for that code, and anonymous callers must not be
+    // handed somebody else's link.
+    pub async fn create_or_reuse(&self, request: NewLink) -> Result<(Link, bool), LinkError> {
+        let reusable = request.reuse_existing
+            && request
+                .alias
+                .as_deref()
+                .is_none_or(|alias| alias.trim().is_empty());
+
+        let link = self.build_link(request).await?;
+
+        if let (true, Some(owner)) = (reusable, link.owner()) {
+            let existing = self
+                .query_service
+                .find_by_owner_and_url(owner, link.user_url().as_str())
+                .await?;
+
+            if let Some(existing) = existing.filter(|found| !found.is_expired(CreatedAt::value())) {
+                return Ok((existing, true));
+            }
+        }
+
+        self.persistence_service.save(link.clone()).await?;
+
+        Ok((link, false))
+    }
+
+    // Every row is validated up front so an atomic batch never touches the
+    // database when any of its rows is malformed.
+    pub async fn create_batch(
+        &self,
+        requests: Vec<NewLink>,
+        mode: BatchMode,
+    ) -> Result<Vec<Result<Link, LinkError>>, LinkError> {
+        let built: Vec<Result<Link, LinkError>> = stream::iter(requests)
+            .map(|request| self.build_link(request))
+            .buffered(BATCH_CONCURRENCY)
+            .collect()
+            .await;
+
+        if mode == BatchMode::Atomic && built.iter().any(|row| row.is_err()) {
+            return Ok(built
+                .into_iter()
+                .map(|row| row.and(Err(LinkError::BatchAborted)))
+                .collect());
+        }
+
+        let valid: Vec<Link> = built
+            .iter()
+            .filter_map(|row| row.as_ref().ok().cloned())
+            .collect();
+
+        let mut saved = self
+            .persistence_service
+            .save_batch(valid, mode)
+            .await?
+            .into_iter();
+
+        Ok(built
+            .into_iter()
+            .map(|row| {
+                let link = row?;
+
+                match saved.next() {
+                    Some(Ok(_)) => Ok(link),
+                    Some(Err(e)) => Err(e),
+                    None => Err(LinkError::LinkCreationError),
+                }
+            })
+            .collect())
+    }
+
+    async fn build_link(&self, request: NewLink) -> Result<Link, LinkError> {
         let link_uuid = LinkId::generate();
         let delete_key = LinkKey::generate()?;
-        let generated_url = ShortUrl::value()?;
         let creation_time = CreatedAt::value();
 
-        let user_url = Url::new(&raw_user_url).map_err(|_| LinkError::EmptyURL)?;
+        let generated_url = match request.alias.as_deref().map(str::trim) {
+            Some(alias) if !alias.is_empty() => ShortUrl::alias(alias)?,
+            _ => ShortUrl::value()?,
+        };
+
+        let utm = request.utm.normalised()?;
+
+        // Hand-typed tracking parameters are stripped before the structured
+        // UTM fields go in, so the latter always survive. Input that does not
+        // parse is passed on untouched for `Url::new` to explain.
+        let destination = match UserUrl::canonical(&request.url, self.strip_tracking_params) {
+            Ok(canonical) => canonical.with_utm(&utm)?.into_inner(),
+            Err(_) => request.url,
+        };
+
+        // Every destination of the link is judged by the same policy, even
+        // if the lists are reloaded halfway through.
+        let policy = self.domain_policy();
+
+        let user_url = Url::new(&destination, self.resolver.as_ref(), &policy).await?;
+
+        self.check_threats(user_url.as_str())?;
+
+        let routing_rules = self
+            .build_routing_rules(request.routing_rules, &policy)
+            .await?;
+        let variants = self.build_variants(request.variants, &policy).await?;
+
+        let expires_at = request
+            .expires_at
+            .map(|value| ExpiresAt::new(value, creation_time))
+            .transpose()?;
+
+        let active_from = request
+            .active_from
+            .map(|value| ActiveFrom::new(value, expires_at.as_ref()))
+            .transpose()?;
+
+        let tags = request
+            .tags
+            .into_iter()
+            .filter(|tag| !tag.trim().is_empty())
+            .map(Tag::try_from)
+            .collect::<Result<Vec<Tag>, LinkError>>()?;
 
         let link = Link::new(
             link_uuid,
-            delete_key.clone().into_inner(),
-            generated_url.clone().into_inner(),
+            delete_key.into_inner(),
+            generated_url.into_inner(),
             user_url.as_str().to_string(),
             creation_time,
         )
-        .map_err(|_| LinkError::LinkCreationError)?;
+        .map_err(|_| LinkError::LinkCreationError)?
+        .with_active_from(active_from)
+        .with_expires_at(expires_at)
+        .with_tags(tags)
+        .with_always_preview(request.always_preview)
+        .with_owner(request.owner)
+        .with_redirect_kind(request.redirect_kind)
+        .with_passthrough(request.passthrough)
+        .with_utm(utm)
+        .with_routing_rules(routing_rules)
+        .with_variants(variants);
 
-        self.persistence_service
-            .save(link)
-            .await
-            .map_err(|e| LinkError::PersistenceError(e.to_string()))?;
+        Ok(link)
+    }
+
+    // Rule destinations go through the same checks as the link's own URL.
+    async fn build_routing_rules(
+        &self,
+        rules: Vec<NewRoutingRule>,
+        policy: &DomainPolicy,
+    ) -> Result<Vec<RoutingRule>, LinkError> {
+        if rules.len() > MAX_RULES_PER_LINK {
+            return Err(LinkError::InvalidRule(format!(
+                "at most {MAX_RULES_PER_LINK} rules per link"
+            )));
+        }
+
+        let destinations = join_all(
+            rules
+                .iter()
+                .map(|rule| Url::new(&rule.url, self.resolver.as_ref(), policy)),
+        )
+        .await;
+
+        rules
+            .into_iter()
+            .zip(destinations)
+            .map(|(rule, destination)| {
+                let destination = UserUrl::canonical(destination?.as_str(), false)?;
+                self.check_threats(destination.as_str())?;
+
+                Ok(RoutingRule::new(rule.conditions.normalised()?, destination))
+            })
+            .collect()
+    }
+
+    async fn build_variants(
+        &self,
+        variants: Vec<NewVariant>,
+        policy: &DomainPolicy,
+    ) -> Result<Vec<Variant>, LinkError> {
+        let destinations = join_all(
+            variants
+                .iter()
+                .map(|variant| Url::new(&variant.url, self.resolver.as_ref(), policy)),
+        )
+        .await;
+
+        let variants = variants
+            .into_iter()
+            .zip(destinations)
+            .map(|(variant, destination)| {
+                let destination = UserUrl::canonical(destination?.as_str(), false)?;
+                self.check_threats(destination.as_str())?;
 
-        Ok(LinkId::from(link_uuid))
+                Variant::new(&variant.name, destination, variant.weight)
+            })
+            .collect::<Result<Vec<Variant>, LinkError>>()?;
+
+        check_variants(&variants)?;
+
+        Ok(variants)
     }
 
+    // Admin removal, which needs no key. Visitors go through
+    // `delete_with_key`.
     pub async fn delete(&self, id: LinkId) -> Result<Option<Link>, LinkError> {
-        let link = self
-            .query_service
-            .find_by_id(id)
-            .await
-            .map_err(|e| LinkError::PersistenceError(e.to_string()))?;
+        let link = self.query_service.find_by_id(id).await?;
 
-        let link_id = link.id();
+        self.persistence_service
+            .delete_by_id(link.id().clone())
+            .await?;
 
-        let delete_hash_key = link.delete_hash_code();
+        Ok(Some(link))
+    }
 
-        let stored_key = self
-            .query_service
-            .find_delete_key(link_id.clone())
-            .await
-            .map_err(|e| LinkError::PersistenceError(e.to_string()))?;
+    pub async fn delete_with_key(
+        &self,
+        id: LinkId,
+        key: LinkKey,
+    ) -> Result<Option<Link>, LinkError> {
+        let link_id = id.clone().into_inner();
+
+        if let Some(retry_after) = self.delete_lockout(&link_id) {
+            return Err(LinkError::TooManyAttempts { retry_after });
+        }
+
+        let stored_key = self.query_service.find_delete_key(id.clone()).await?;
 
-        if delete_hash_key != &stored_key {
+        if !key_matches(key.value(), stored_key.value()) {
+            self.record_delete_failure(link_id);
             return Err(LinkError::HashedCodeMismatch);
         }
 
+        self.failed_deletes().remove(&link_id);
+
+        self.persistence_service.delete_by_id(id).await
+    }
+
+    fn failed_deletes(&self) -> MutexGuard<'_, HashMap<Uuid, (u32, Instant)>> {
+        self.delete_failures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Seconds until the link takes delete keys again, if it is locked out.
+    fn delete_lockout(&self, link_id: &Uuid) -> Option<u64> {
+        let failures = self.failed_deletes();
+        let (count, since) = failures.get(link_id)?;
+
+        let remaining = DELETE_LOCKOUT.checked_sub(since.elapsed())?;
+
+        (*count >= MAX_DELETE_FAILURES).then_some(remaining.as_secs())
+    }
+
+    // Windows that have run out are dropped here, so the map only holds
+    // links someone is guessing at right now.
+    fn record_delete_failure(&self, link_id: Uuid) {
+        let mut failures = self.failed_deletes();
+
+        failures.retain(|_, (_, since)| since.elapsed() < DELETE_LOCKOUT);
+
+        failures
+            .entry(link_id)
+            .or_insert_with(|| (0, Instant::now()))
+            .0 += 1;
+    }
+
+    pub async fn find(&self, id: LinkId) -> Result<Link, LinkError> {
+        self.query_service.find_by_id(id).await
+    }
+
+    pub async fn find_by_code(&self, code: ShortUrl) -> Result<Link, LinkError> {
+        self.query_service.find_by_short_code(code).await
+    }
+
+    pub async fn list(
+        &self,
+        offset: i64,
+        limit: i64,
+        status: Option<LinkStatus>,
+    ) -> Result<Vec<Link>, LinkError> {
+        self.query_service.list(offset, limit, status).await
+    }
+
+    pub async fn search(
+        &self,
+        term: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Link>, LinkError> {
+        let term = term.map(str::trim).filter(|term| !term.is_empty());
+
+        self.query_service.search(term, offset, limit).await
+    }
+
+    // The record and its clicks are kept either way so a takedown can be
+    // investigated; only an `Active` link resolves.
+    pub async fn change_status(
+        &self,
+        id: LinkId,
+        next: LinkStatus,
+        reason: Option<String>,
+    ) -> Result<Link, LinkError> {
+        let link = self.query_service.find_by_id(id.clone()).await?;
+
+        if !link.status().can_become(next) {
+            return Err(LinkError::InvalidTransition {
+                from: link.status().as_str(),
+                to: next.as_str(),
+            });
+        }
+
+        let reason = match next {
+            LinkStatus::Active => None,
+            _ => reason
+                .map(|reason| reason.trim().to_string())
+                .filter(|reason| !reason.is_empty()),
+        };
+
+        if reason
+            .as_deref()
+            .is_some_and(|reason| reason.chars().count() > MAX_STATUS_REASON_LEN)
+        {
+            return Err(LinkError::InvalidReason);
+        }
+
         self.persistence_service
-            .delete_by_id(link_id.clone())
-            .await
-            .map_err(|e| LinkError::PersistenceError(e.to_string()))?;
+            .set_status(id, next, reason.clone())
+            .await?;
 
-        Ok(Some(link))
+        Ok(link.with_status(next).with_status_reason(reason))
+    }
+
+    pub async fn disable(&self, id: LinkId, reason: Option<String>) -> Result<Link, LinkError> {
+        self.change_status(id, LinkStatus::Disabled, reason).await
     }
 
-    pub async fn redirect(&self, code: ShortUrl) -> Result<Link, LinkError> {
-        let link = self
+    pub async fn block(&self, id: LinkId, reason: Option<String>) -> Result<Link, LinkError> {
+        self.change_status(id, LinkStatus::Blocked, reason).await
+    }
+
+    pub async fn enable(&self, id: LinkId) -> Result<Link, LinkError> {
+        self.change_status(id, LinkStatus::Active, None).await
+    }
+
+    pub async fn stats(&self, id: LinkId) -> Result<ClickStats, LinkError> {
+        self.query_service.click_stats(id).await
+    }
+
+    // One entry per day for the last `days` days, today included, with
+    // quiet days filled in as zero.
+    pub async fn daily_clicks(&self, id: LinkId, days: u32) -> Result<Vec<DailyClicks>, LinkError> {
+        let today = CreatedAt::value().date_naive();
+        let first = today - Duration::days(i64::from(days.max(1)) - 1);
+
+        let counted = self
             .query_service
-            .find_by_short_code(code)
+            .daily_clicks(id, first.and_time(NaiveTime::MIN).and_utc())
+            .await?;
+
+        Ok(first
+            .iter_days()
+            .take_while(|day| *day <= today)
+            .map(|day| DailyClicks {
+                day,
+                clicks: counted
+                    .iter()
+                    .find(|counted| counted.day == day)
+                    .map_or(0, |counted| counted.clicks),
+            })
+            .collect())
+    }
+
+    pub async fn campaign_stats(&self) -> Result<Vec<CampaignStats>, LinkError> {
+        self.query_service.campaign_stats().await
+    }
+
+    // Reports are accepted for links in any state so nothing a visitor sees
+    // hints at whether others reported it too.
+    pub async fn report(
+        &self,
+        code: ShortUrl,
+        request: NewReport,
+    ) -> Result<ReportOutcome, LinkError> {
+        let link = self.query_service.find_by_short_code(code).await?;
+
+        // Without an address every anonymous report would look like the
+        // same reporter, so duplicates could no longer be told apart.
+        let reporter_ip = request.reporter_ip.ok_or_else(|| {
+            LinkError::InvalidReport("the reporter's address is unknown".to_string())
+        })?;
+
+        let report = Report::new(
+            link.id().clone(),
+            ReportReason::try_from(request.reason.as_str())?,
+            request.details,
+            reporter_hash(&self.report_salt, reporter_ip),
+            CreatedAt::value(),
+        )?;
+
+        let recorded = self.persistence_service.save_report(report).await?;
+
+        if !recorded {
+            return Ok(ReportOutcome::Duplicate);
+        }
+
+        if self.report_threshold > 0 && link.status() == LinkStatus::Active {
+            let open = self
+                .query_service
+                .count_open_reports(link.id().clone())
+                .await?;
+
+            if open >= self.report_threshold {
+                self.disable(
+                    link.id().clone(),
+                    Some(format!("Disabled automatically after {open} abuse reports")),
+                )
+                .await?;
+            }
+        }
+
+        Ok(ReportOutcome::Recorded)
+    }
+
+    pub async fn list_reports(
+        &self,
+        state: Option<ReportState>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Report>, LinkError> {
+        self.query_service.list_reports(state, offset, limit).await
+    }
+
+    // Confirming blocks the link; dismissing leaves its status alone, so a
+    // link auto-disabled by reports has to be re-enabled separately.
+    pub async fn review_report(
+        &self,
+        id: Uuid,
+        decision: ReportState,
+    ) -> Result<Report, LinkError> {
+        let report = self.query_service.find_report(id).await?;
+
+        if report.state() != ReportState::Open || decision == ReportState::Open {
+            return Err(LinkError::InvalidTransition {
+                from: report.state().as_str(),
+                to: decision.as_str(),
+            });
+        }
+
+        self.persistence_service
+            .set_report_state(id, decision)
+            .await?;
+
+        if decision == ReportState::Confirmed {
+            let link = self.find(report.link_id().clone()).await?;
+
+            if link.status() != LinkStatus::Blocked {
+                self.block(
+                    link.id().clone(),
+                    Some(format!(
+                        "Abuse report confirmed ({})",
+                        report.reason().as_str()
+                    )),
+                )
+                .await?;
+            }
+        }
+
+        Ok(report.with_state(decision))
+    }
+
+    pub async fn purge_expired(&self) -> Result<u64, LinkError> {
+        self.persistence_service
+            .purge_expired(CreatedAt::value())
             .await
-            .map_err(|e| LinkError::PersistenceError(e.to_string()))?;
+    }
 
-        Ok(link)
+    // Looks up a link that is allowed to resolve right now.
+    //
+    // The list may have grown since the link was created. A hit keeps
+    // showing the warning rather than a bare "unavailable" page. Only an
+    // active link is disabled, so a reason an admin already gave stays.
+    //
+    // Links created before a deny rule existed are taken offline the first
+    // time someone follows them. This is policy, not a moderation verdict,
+    // so the link is disabled rather than blocked and an admin can re-enable
+    // it once the rule is lifted.
+    async fn resolve(&self, code: ShortUrl) -> Result<Link, LinkError> {
+        let link = self.query_service.find_by_short_code(code).await?;
+
+        let refusal = match self.availability(&link) {
+            Ok(()) => return Ok(link),
+            Err(refusal) => refusal,
+        };
+
+        if link.status() == LinkStatus::Active {
+            let reason = match &refusal {
+                LinkError::ThreatDetected(threat_type) => {
+                    Some(format!("Destination is on the threat list ({threat_type})"))
+                }
+                // An active link is only refused as disabled by the policy.
+                LinkError::Disabled(reason) => reason.clone(),
+                _ => None,
+            };
+
+            if let Some(reason) = reason {
+                self.persistence_service
+                    .set_status(link.id().clone(), LinkStatus::Disabled, Some(reason))
+                    .await?;
+            }
+        }
+
+        Err(refusal)
+    }
+
+    // Decides whether a link may resolve without changing anything; `resolve`
+    // persists what this finds.
+    fn availability(&self, link: &Link) -> Result<(), LinkError> {
+        self.check_threats(link.user_url().as_str())?;
+
+        match link.status() {
+            LinkStatus::Active => {}
+            LinkStatus::Disabled => {
+                return Err(LinkError::Disabled(
+                    link.status_reason().map(str::to_string),
+                ))
+            }
+            LinkStatus::Blocked => {
+                return Err(LinkError::Blocked(link.status_reason().map(str::to_string)))
+            }
+        }
+
+        let now = CreatedAt::value();
+
+        if link.is_expired(now) {
+            return Err(LinkError::Expired);
+        }
+
+        if let Some(active_from) = link
+            .active_from()
+            .filter(|active_from| !active_from.has_arrived(now))
+        {
+            return Err(LinkError::NotYetActive(active_from.clone().into_inner()));
+        }
+
+        if self.denies_destination(link.user_url().as_str()) {
+            return Err(LinkError::Disabled(Some(
+                "Destination domain is not allowed".to_string(),
+            )));
+        }
+
+        Ok(())
+    }
+
+    // A QR code is only handed out for a link that would resolve. Scheduled
+    // links are the exception, since codes are often printed before launch.
+    // Rendering one is a read: any status change waits for a real visit.
+    pub async fn printable(&self, code: ShortUrl) -> Result<Link, LinkError> {
+        let link = self.query_service.find_by_short_code(code).await?;
+
+        match self.availability(&link) {
+            Ok(()) | Err(LinkError::NotYetActive(_)) => Ok(link),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Previews never count as a click; only following the link does.
+    //
+    // Routing rules pick the destination for this visitor; `extra_path` and
+    // `query` are then applied to passthrough links. Anything other than the
+    // link's own URL is checked against the threat list and the deny rules
+    // again since it was not part of the link-level check in `resolve`. A
+    // denied destination only refuses this visit: visitors routed elsewhere
+    // still get through, so the link is left as it is.
+    pub async fn visit(
+        &self,
+        code: ShortUrl,
+        extra_path: Option<&str>,
+        query: Option<&str>,
+        context: &RequestContext,
+        mode: VisitMode,
+    ) -> Result<Visit, LinkError> {
+        let link = self.resolve(code).await?;
+
+        let preview = match mode {
+            VisitMode::Preview => true,
+            VisitMode::Auto => link.always_preview(),
+            VisitMode::Follow => false,
+        };
+
+        if preview {
+            let stats = self.stats(link.id().clone()).await?;
+            return Ok(Visit::Preview(link, stats));
+        }
+
+        // Without passthrough a longer path names nothing.
+        if !link.passthrough() && extra_path.is_some_and(|extra| !extra.is_empty()) {
+            return Err(LinkError::NotFound);
+        }
+
+        let audience = context.audience(self.geo_locator.as_deref());
+
+        let total = total_weight(link.variants());
+        let roll = if total > 0 {
+            rand::thread_rng().gen_range(0..total)
+        } else {
+            0
+        };
+
+        let (routed, variant) =
+            link.destination_for(&audience, context.sticky_variant.as_deref(), roll);
+        let variant = variant.map(|variant| variant.name().to_string());
+
+        let destination = if link.passthrough() {
+            routed.with_passthrough(extra_path, query, self.query_conflict)?
+        } else {
+            routed.clone()
+        };
+
+        if &destination != link.user_url() {
+            self.check_threats(destination.as_str())?;
+
+            if self.denies_destination(destination.as_str()) {
+                return Err(LinkError::InvalidUrl(UrlError::BlockedDomain));
+            }
+        }
+
+        let now = CreatedAt::value();
+
+        // A lost click must never cost the visitor their redirect.
+        if let Err(e) = self
+            .persistence_service
+            .record_clicks(vec![
+                Click::new(link.id().clone(), now).with_variant(variant.clone())
+            ])
+            .await
+        {
+            tracing::warn!("failed to record click: {e}");
+        }
+
+        Ok(Visit::Redirect(RedirectTarget {
+            link,
+            destination,
+            variant,
+        }))
+    }
+
+    pub async fn export_page(
+        &self,
+        offset: i64,
+        limit: i64,
+        include_clicks: bool,
+    ) -> Result<Vec<LinkEntry>, LinkError> {
+        let links = self.query_service.list(offset, limit, None).await?;
+
+        let mut entries = Vec::with_capacity(links.len());
+
+        for link in links {
+            let clicks = if include_clicks {
+                self.query_service.find_clicks(link.id().clone()).await?
+            } else {
+                Vec::new()
+            };
+
+            entries.push(LinkEntry { link, clicks });
+        }
+
+        Ok(entries)
+    }
+
+    // Imported links keep their original id, short code, creation time and
+    // delete key; anything that would overwrite an existing row is reported
+    // as a conflict and skipped.
+    pub async fn import(
+        &self,
+        entries: Vec<LinkEntry>,
+        dry_run: bool,
+    ) -> Result<Vec<ImportOutcome>, LinkError> {
+        let mut seen_ids = HashSet::new();
+        let mut seen_codes = HashSet::new();
+        let mut outcomes = Vec::with_capacity(entries.len());
+
+        for entry in entries {
+            let id = entry.link.id().clone();
+            let code = entry.link.short_url().clone();
+
+            if !seen_ids.insert(id.clone().into_inner())
+                || exists(self.query_service.find_by_id(id).await)?
+            {
+                outcomes.push(ImportOutcome::Conflict(ImportConflict::IdExists));
+                continue;
+            }
+
+            if !seen_codes.insert(code.as_str().to_string())
+                || exists(self.query_service.find_by_short_code(code).await)?
+            {
+                outcomes.push(ImportOutcome::Conflict(ImportConflict::ShortCodeExists));
+                continue;
+            }
+
+            if let Err(e) = self.check_destinations(&entry.link).await {
+                outcomes.push(ImportOutcome::Failed(e));
+                continue;
+            }
+
+            if dry_run {
+                outcomes.push(ImportOutcome::WouldImport);
+                continue;
+            }
+
+            let outcome = match self
+                .persistence_service
+                .save_with_clicks(entry.link, entry.clicks)
+                .await
+            {
+                Ok(_) => ImportOutcome::Imported,
+                Err(LinkError::AliasTaken) => {
+                    ImportOutcome::Conflict(ImportConflict::ShortCodeExists)
+                }
+                Err(e) => ImportOutcome::Failed(e),
+            };
+
+            outcomes.push(outcome);
+        }
+
+        Ok(outcomes)
     }
 }
@@ -0,0 +1,234 @@
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use url::{Host, Url as ExternalUrl};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreatMatch {
+    pub threat_type: String,
+    pub expression: String,
+}
+
+// Hash prefixes in the Safe Browsing style: the list never contains a
+// readable URL, only the leading bytes of SHA-256 digests of URL
+// expressions such as `evil.example/login/`.
+//
+// Entries are bucketed by their first four bytes, the shortest prefix the
+// list accepts, so a digest finds its candidates with one lookup.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThreatList {
+    prefixes: HashMap<[u8; PREFIX_KEY_LEN], Vec<(Vec<u8>, String)>>,
+    len: usize,
+}
+
+const PREFIX_KEY_LEN: usize = 4;
+
+fn prefix_key(bytes: &[u8]) -> [u8; PREFIX_KEY_LEN] {
+    let mut key = [0; PREFIX_KEY_LEN];
+    key.copy_from_slice(&bytes[..PREFIX_KEY_LEN]);
+    key
+}
+
+impl ThreatList {
+    // One hex prefix (4 to 32 bytes) per line, optionally followed by a
+    // threat type; `#` starts a comment.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut list = Self::default();
+
+        for (index, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let prefix = parts.next().unwrap_or_default();
+            let threat_type = parts.next().unwrap_or("UNSPECIFIED").to_ascii_uppercase();
+
+            let bytes = hex::decode(prefix)
+                .ok()
+                .filter(|bytes| (PREFIX_KEY_LEN..=32).contains(&bytes.len()))
+                .ok_or_else(|| {
+                    format!(
+                        "line {}: '{prefix}' is not a 4-32 byte hex prefix",
+                        index + 1
+                    )
+                })?;
+
+            let bucket = list.prefixes.entry(prefix_key(&bytes)).or_default();
+
+            match bucket.iter_mut().find(|(listed, _)| *listed == bytes) {
+                Some(entry) => entry.1 = threat_type,
+                None => {
+                    bucket.push((bytes, threat_type));
+                    list.len += 1;
+                }
+            }
+        }
+
+        Ok(list)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn check(&self, url: &str) -> Option<ThreatMatch> {
+        if self.prefixes.is_empty() {
+            return None;
+        }
+
+        expressions(url).into_iter().find_map(|expression| {
+            let digest = Sha256::digest(expression.as_bytes());
+
+            self.prefixes
+                .get(&prefix_key(&digest))?
+                .iter()
+                .find(|(prefix, _)| digest.starts_with(prefix))
+                .map(|(_, threat_type)| ThreatMatch {
+                    threat_type: threat_type.clone(),
+                    expression: expression.clone(),
+                })
+        })
+    }
+}
+
+// Host suffix / path prefix combinations, following the Safe Browsing
+// lookup rules: at most five hosts and six paths per URL.
+pub fn expressions(url: &str) -> Vec<String> {
+    let Ok(parsed) = ExternalUrl::parse(url) else {
+        return Vec::new();
+    };
+
+    let hosts: Vec<String> = match parsed.host() {
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            let labels: Vec<&str> = domain.split('.').collect();
+
+            let mut hosts = vec![domain.clone()];
+            let first_suffix = labels.len().saturating_sub(5).max(1);
+
+            for start in first_suffix..labels.len().saturating_sub(1) {
+                hosts.push(labels[start..].join("."));
+            }
+
+            hosts.dedup();
+            hosts
+        }
+        Some(host) => vec![host.to_string()],
+        None => return Vec::new(),
+    };
+
+    let path = parsed.path();
+    let mut paths = Vec::new();
+
+    if let Some(query) = parsed.query() {
+        paths.push(format!("{path}?{query}"));
+    }
+
+    paths.push(path.to_string());
+
+    // Only directories become prefixes; the full path is already listed.
+    let mut segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if !path.ends_with('/') {
+        segments.pop();
+    }
+
+    let mut prefix = String::from("/");
+    paths.push(prefix.clone());
+
+    for segment in segments.iter().take(3) {
+        prefix.push_str(segment);
+        prefix.push('/');
+        paths.push(prefix.clone());
+    }
+
+    let mut seen = Vec::new();
+    for path in paths {
+        if !seen.contains(&path) {
+            seen.push(path);
+        }
+    }
+
+    hosts
+        .iter()
+        .flat_map(|host| seen.iter().map(move |path| format!("{host}{path}")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prefix_of(expression: &str) -> String {
+        hex::encode(&Sha256::digest(expression.as_bytes())[..4])
+    }
+
+    #[test]
+    fn builds_host_and_path_expressions() {
+        let found = expressions("https://a.b.evil.example/1/2.html?x=1");
+
+        for expected in [
+            "a.b.evil.example/1/2.html?x=1",
+            "a.b.evil.example/1/2.html",
+            "a.b.evil.example/",
+            "a.b.evil.example/1/",
+            "b.evil.example/",
+            "evil.example/1/2.html",
+        ] {
+            assert!(found.contains(&expected.to_string()), "{expected}");
+        }
+
+        assert!(!found
+            .iter()
+            .any(|expression| expression.starts_with("example/")));
+    }
+
+    #[test]
+    fn matches_any_expression_of_a_listed_site() {
+        let list = ThreatList::parse(&format!(
+            "# local feed\n{} SOCIAL_ENGINEERING\n",
+            prefix_of("evil.example/")
+        ))
+        .unwrap();
+
+        let hit = list
+            .check("https://login.evil.example/account?next=1")
+            .unwrap();
+        assert_eq!(hit.threat_type, "SOCIAL_ENGINEERING");
+        assert_eq!(hit.expression, "evil.example/");
+
+        assert_eq!(list.check("https://example.com/evil.example/"), None);
+    }
+
+    #[test]
+    fn matches_prefixes_longer_than_the_bucket_key() {
+        let digest = Sha256::digest("evil.example/".as_bytes());
+        let mut near_miss = digest[..8].to_vec();
+        near_miss[7] ^= 0xff;
+
+        let list = ThreatList::parse(&format!(
+            "{} MALWARE\n{} UNWANTED_SOFTWARE\n",
+            hex::encode(near_miss),
+            hex::encode(&digest[..8])
+        ))
+        .unwrap();
+
+        assert_eq!(list.len(), 2);
+        assert_eq!(
+            list.check("https://evil.example/").unwrap().threat_type,
+            "UNWANTED_SOFTWARE"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_prefixes() {
+        assert!(ThreatList::parse("abc").is_err());
+        assert!(ThreatList::parse("zzzzzzzz MALWARE").is_err());
+        assert!(ThreatList::parse("").unwrap().is_empty());
+    }
+}
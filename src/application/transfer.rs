@@ -0,0 +1,22 @@
+use crate::domain::{click::Click, errors::LinkError, link::Link};
+
+// A link together with its click history, as moved between environments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkEntry {
+    pub link: Link,
+    pub clicks: Vec<Click>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportConflict {
+    IdExists,
+    ShortCodeExists,
+}
+
+#[derive(Debug, Clone)]
+pub enum ImportOutcome {
+    Imported,
+    WouldImport,
+    Conflict(ImportConflict),
+    Failed(LinkError),
+}
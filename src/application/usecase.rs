@@ -1,8 +1,12 @@
 use crate::domain::{
+    click::{CampaignStats, Click, ClickStats, DailyClicks},
     errors::LinkError,
-    link::{Link, LinkId, LinkKey, ShortUrl},
-    ports::{LinkPersistence, LinkQuery},
+    link::{Link, LinkId, LinkKey, LinkStatus, ShortUrl},
+    ports::{BatchMode, LinkPersistence, LinkQuery},
+    report::{Report, ReportState},
 };
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct LinkPersistenceService<P: LinkPersistence + Send + Sync> {
@@ -21,6 +25,47 @@ impl<P: LinkPersistence + Send + Sync> LinkPersistenceService<P> {
     pub async fn delete_by_id(&self, id: LinkId) -> Result<Option<Link>, LinkError> {
         self.persistence.delete_by_id(id).await
     }
+
+    pub async fn save_batch(
+        &self,
+        links: Vec<Link>,
+        mode: BatchMode,
+    ) -> Result<Vec<Result<LinkId, LinkError>>, LinkError> {
+        self.persistence.save_batch(links, mode).await
+    }
+
+    pub async fn record_clicks(&self, clicks: Vec<Click>) -> Result<(), LinkError> {
+        self.persistence.record_clicks(clicks).await
+    }
+
+    pub async fn save_with_clicks(
+        &self,
+        link: Link,
+        clicks: Vec<Click>,
+    ) -> Result<LinkId, LinkError> {
+        self.persistence.save_with_clicks(link, clicks).await
+    }
+
+    pub async fn set_status(
+        &self,
+        id: LinkId,
+        status: LinkStatus,
+        reason: Option<String>,
+    ) -> Result<(), LinkError> {
+        self.persistence.set_status(id, status, reason).await
+    }
+
+    pub async fn purge_expired(&self, now: DateTime<Utc>) -> Result<u64, LinkError> {
+        self.persistence.purge_expired(now).await
+    }
+
+    pub async fn save_report(&self, report: Report) -> Result<bool, LinkError> {
+        self.persistence.save_report(report).await
+    }
+
+    pub async fn set_report_state(&self, id: Uuid, state: ReportState) -> Result<(), LinkError> {
+        self.persistence.set_report_state(id, state).await
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -44,4 +89,67 @@ impl<Q: LinkQuery + Send + Sync> LinkQueryService<Q> {
     pub async fn find_by_short_code(&self, short_code: ShortUrl) -> Result<Link, LinkError> {
         self.query.find_by_short_code(short_code).await
     }
+
+    pub async fn list(
+        &self,
+        offset: i64,
+        limit: i64,
+        status: Option<LinkStatus>,
+    ) -> Result<Vec<Link>, LinkError> {
+        self.query.list(offset, limit, status).await
+    }
+
+    pub async fn search(
+        &self,
+        term: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Link>, LinkError> {
+        self.query.search(term, offset, limit).await
+    }
+
+    pub async fn find_by_owner_and_url(
+        &self,
+        owner: &str,
+        long_url: &str,
+    ) -> Result<Option<Link>, LinkError> {
+        self.query.find_by_owner_and_url(owner, long_url).await
+    }
+
+    pub async fn find_clicks(&self, id: LinkId) -> Result<Vec<Click>, LinkError> {
+        self.query.find_clicks(id).await
+    }
+
+    pub async fn click_stats(&self, id: LinkId) -> Result<ClickStats, LinkError> {
+        self.query.click_stats(id).await
+    }
+
+    pub async fn daily_clicks(
+        &self,
+        id: LinkId,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<DailyClicks>, LinkError> {
+        self.query.daily_clicks(id, since).await
+    }
+
+    pub async fn campaign_stats(&self) -> Result<Vec<CampaignStats>, LinkError> {
+        self.query.campaign_stats().await
+    }
+
+    pub async fn find_report(&self, id: Uuid) -> Result<Report, LinkError> {
+        self.query.find_report(id).await
+    }
+
+    pub async fn list_reports(
+        &self,
+        state: Option<ReportState>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Report>, LinkError> {
+        self.query.list_reports(state, offset, limit).await
+    }
+
+    pub async fn count_open_reports(&self, link_id: LinkId) -> Result<i64, LinkError> {
+        self.query.count_open_reports(link_id).await
+    }
 }
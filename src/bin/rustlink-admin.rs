@@ -0,0 +1,594 @@
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+
+use rustlink::application::{
+    command::NewLink,
+    service::LinkService,
+    usecase::{LinkPersistenceService, LinkQueryService},
+};
+use rustlink::domain::{
+    click::ClickStats,
+    link::{Link, LinkId, LinkKey, LinkStatus, RedirectKind, ShortUrl, Utm},
+};
+use rustlink::infrastructure::{
+    config::AppConfig,
+    lists::reload_lists,
+    repository::PgPoolRepository,
+    resolver::TokioResolver,
+    rules::{RuleRecord, VariantRecord},
+    transfer::{decode_entries, encode_entries, ImportReport, TransferFormat},
+};
+
+type AdminService = LinkService<PgPoolRepository, PgPoolRepository>;
+
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+#[derive(Parser)]
+#[command(name = "rustlink-admin", about = "Operate a rustlink database")]
+struct Cli {
+    #[arg(long, value_enum, default_value_t = Output::Table, global = true)]
+    output: Output,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Output {
+    Table,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Jsonl,
+    Csv,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Status {
+    Active,
+    Disabled,
+    Blocked,
+}
+
+impl From<Status> for LinkStatus {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Active => LinkStatus::Active,
+            Status::Disabled => LinkStatus::Disabled,
+            Status::Blocked => LinkStatus::Blocked,
+        }
+    }
+}
+
+impl From<Format> for TransferFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Jsonl => TransferFormat::Jsonl,
+            Format::Csv => TransferFormat::Csv,
+        }
+    }
+}
+
+#[derive(Args)]
+struct CreateArgs {
+    url: String,
+    #[arg(long)]
+    alias: Option<String>,
+    /// RFC 3339 timestamp before which the link shows "coming soon"
+    #[arg(long)]
+    active_from: Option<DateTime<Utc>>,
+    /// RFC 3339 timestamp after which the link stops resolving
+    #[arg(long)]
+    expires_at: Option<DateTime<Utc>>,
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+    /// Always show the preview page instead of redirecting
+    #[arg(long)]
+    preview: bool,
+    /// Redirect status (301, 302, 307 or 308) instead of the server default
+    #[arg(long)]
+    redirect: Option<u16>,
+    /// Forward extra path segments and the query string to the destination
+    #[arg(long)]
+    passthrough: bool,
+    #[arg(long)]
+    utm_source: Option<String>,
+    #[arg(long)]
+    utm_medium: Option<String>,
+    #[arg(long)]
+    utm_campaign: Option<String>,
+    #[arg(long)]
+    utm_term: Option<String>,
+    #[arg(long)]
+    utm_content: Option<String>,
+    /// JSON file with an ordered array of routing rules
+    #[arg(long)]
+    rules: Option<String>,
+    /// JSON file with an array of weighted A/B variants
+    #[arg(long)]
+    variants: Option<String>,
+    /// Create the link on behalf of this API key owner
+    #[arg(long)]
+    owner: Option<String>,
+    /// Return the owner's existing link for the same destination
+    #[arg(long, requires = "owner")]
+    reuse: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Shorten a URL
+    Create(Box<CreateArgs>),
+    /// Show a link by id or short code
+    Get { link: String },
+    /// List links in creation order
+    List {
+        #[arg(long, default_value_t = 0)]
+        offset: i64,
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+        /// Only show links in this state
+        #[arg(long, value_enum)]
+        status: Option<Status>,
+    },
+    /// Delete a link by id
+    Delete {
+        id: String,
+        /// Delete key handed out at creation
+        #[arg(long, required_unless_present = "force")]
+        key: Option<String>,
+        /// Skip the delete key check
+        #[arg(long)]
+        force: bool,
+    },
+    /// Stop a link from resolving without deleting it
+    Disable {
+        link: String,
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Take a link down after an abuse report; only `enable` lifts it
+    Block {
+        link: String,
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Let a disabled or blocked link resolve again
+    Enable { link: String },
+    /// Show click statistics for a link
+    Stats { link: String },
+    /// Show clicks grouped by UTM campaign
+    Campaigns,
+    /// Write every link to stdout or a file
+    Export {
+        #[arg(long, value_enum, default_value_t = Format::Jsonl)]
+        format: Format,
+        #[arg(long)]
+        clicks: bool,
+        #[arg(long)]
+        to: Option<String>,
+    },
+    /// Load links from a file, or stdin when given `-`
+    Import {
+        from: String,
+        #[arg(long, value_enum, default_value_t = Format::Jsonl)]
+        format: Format,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Apply pending database migrations
+    Migrate,
+    /// Delete every link whose expiry has passed
+    PurgeExpired,
+}
+
+#[derive(Serialize)]
+struct LinkView {
+    id: String,
+    short_code: String,
+    long_url: String,
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status_reason: Option<String>,
+    created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    active_from: Option<String>,
+    expires_at: Option<String>,
+    tags: Vec<String>,
+}
+
+impl From<&Link> for LinkView {
+    fn from(link: &Link) -> Self {
+        Self {
+            id: link.id().clone().into_inner().to_string(),
+            short_code: link.short_url().as_str().to_string(),
+            long_url: link.user_url().as_str().to_string(),
+            status: link.status().as_str(),
+            status_reason: link.status_reason().map(str::to_string),
+            created_at: format_dt(link.clone().created_at().into_inner()),
+            active_from: link
+                .active_from()
+                .map(|value| format_dt(value.clone().into_inner())),
+            expires_at: link
+                .expires_at()
+                .map(|value| format_dt(value.clone().into_inner())),
+            tags: link
+                .tags()
+                .iter()
+                .map(|tag| tag.as_str().to_string())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct StatsView {
+    id: String,
+    short_code: String,
+    total: i64,
+    first_clicked_at: Option<String>,
+    last_clicked_at: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    by_variant: Vec<VariantClicksView>,
+}
+
+#[derive(Serialize)]
+struct VariantClicksView {
+    variant: String,
+    clicks: i64,
+}
+
+#[derive(Serialize)]
+struct CampaignView {
+    campaign: String,
+    links: i64,
+    clicks: i64,
+    last_clicked_at: Option<String>,
+}
+
+fn format_dt(value: DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn print_links(output: Output, links: &[Link]) -> Result<(), Box<dyn std::error::Error>> {
+    let views: Vec<LinkView> = links.iter().map(LinkView::from).collect();
+
+    match output {
+        Output::Json => println!("{}", serde_json::to_string_pretty(&views)?),
+        Output::Table => {
+            println!(
+                "{:<36}  {:<10}  {:<8}  {:<20}  {:<20}  URL",
+                "ID", "CODE", "STATUS", "CREATED", "EXPIRES"
+            );
+
+            for view in views {
+                println!(
+                    "{:<36}  {:<10}  {:<8}  {:<20}  {:<20}  {}",
+                    view.id,
+                    view.short_code,
+                    view.status,
+                    view.created_at,
+                    view.expires_at.unwrap_or_else(|| "-".to_string()),
+                    view.long_url
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Operators paste whichever identifier they have at hand.
+async fn resolve(service: &AdminService, link: &str) -> Result<Link, Box<dyn std::error::Error>> {
+    let link = match LinkId::from_string(link.to_string()) {
+        Ok(id) => service.find(id).await?,
+        Err(_) => {
+            let code = ShortUrl::try_from(link.to_string())?;
+            service.find_by_code(code).await?
+        }
+    };
+
+    Ok(link)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenvy::dotenv().ok();
+
+    let cli = Cli::parse();
+    let config = AppConfig::from_env();
+
+    let repo = PgPoolRepository::connect(&config.database_url, config.db_timeout).await?;
+
+    if let Command::Migrate = cli.command {
+        repo.migrate().await?;
+        println!("Migrations applied");
+        return Ok(());
+    }
+
+    let service = Arc::new(
+        AdminService::new(
+            LinkPersistenceService::new(repo.clone()),
+            LinkQueryService::new(repo),
+            Arc::new(TokioResolver::new(config.dns_timeout)),
+        )
+        .await
+        .with_strip_tracking_params(config.strip_tracking_params)
+        .with_query_conflict(config.query_conflict),
+    );
+
+    reload_lists(&service, &config)?;
+
+    match cli.command {
+        Command::Create(args) => {
+            let CreateArgs {
+                url,
+                alias,
+                active_from,
+                expires_at,
+                tags,
+                preview,
+                redirect,
+                passthrough,
+                utm_source,
+                utm_medium,
+                utm_campaign,
+                utm_term,
+                utm_content,
+                rules,
+                variants,
+                owner,
+                reuse,
+            } = *args;
+
+            let routing_rules = match rules {
+                Some(path) => {
+                    serde_json::from_str::<Vec<RuleRecord>>(&std::fs::read_to_string(path)?)?
+                        .into_iter()
+                        .map(RuleRecord::into_new_rule)
+                        .collect::<Result<_, _>>()?
+                }
+                None => Vec::new(),
+            };
+
+            let variants = match variants {
+                Some(path) => {
+                    serde_json::from_str::<Vec<VariantRecord>>(&std::fs::read_to_string(path)?)?
+                        .into_iter()
+                        .map(VariantRecord::into_new_variant)
+                        .collect()
+                }
+                None => Vec::new(),
+            };
+
+            let (link, reused) = service
+                .create_or_reuse(NewLink {
+                    url,
+                    alias,
+                    active_from,
+                    expires_at,
+                    tags,
+                    always_preview: preview,
+                    redirect_kind: redirect.map(RedirectKind::try_from).transpose()?,
+                    passthrough,
+                    utm: Utm {
+                        source: utm_source,
+                        medium: utm_medium,
+                        campaign: utm_campaign,
+                        term: utm_term,
+                        content: utm_content,
+                    },
+                    routing_rules,
+                    variants,
+                    owner,
+                    reuse_existing: reuse,
+                })
+                .await?;
+
+            print_links(cli.output, std::slice::from_ref(&link))?;
+
+            if reused {
+                eprintln!("Reused existing link");
+            }
+            eprintln!("Delete key: {}", link.delete_hash_code().value());
+        }
+        Command::Get { link } => {
+            let link = resolve(&service, &link).await?;
+            print_links(cli.output, &[link])?;
+        }
+        Command::List {
+            offset,
+            limit,
+            status,
+        } => {
+            let links = service.list(offset, limit, status.map(Into::into)).await?;
+            print_links(cli.output, &links)?;
+        }
+        Command::Delete { id, key, force } => {
+            let id = LinkId::from_string(id)?;
+
+            let deleted = match (force, key) {
+                (true, _) => service.delete(id).await?,
+                (false, Some(key)) => service.delete_with_key(id, LinkKey::new(key)).await?,
+                (false, None) => return Err("either --key or --force is required".into()),
+            };
+
+            match deleted {
+                Some(link) => println!("Deleted {}", link.short_url().as_str()),
+                None => println!("Nothing to delete"),
+            }
+        }
+        Command::Disable { link, reason } => {
+            let link = resolve(&service, &link).await?;
+            service.disable(link.id().clone(), reason).await?;
+            println!("Disabled {}", link.short_url().as_str());
+        }
+        Command::Block { link, reason } => {
+            let link = resolve(&service, &link).await?;
+            service.block(link.id().clone(), reason).await?;
+            println!("Blocked {}", link.short_url().as_str());
+        }
+        Command::Enable { link } => {
+            let link = resolve(&service, &link).await?;
+            service.enable(link.id().clone()).await?;
+            println!("Enabled {}", link.short_url().as_str());
+        }
+        Command::Stats { link } => {
+            let link = resolve(&service, &link).await?;
+            let ClickStats {
+                total,
+                first_clicked_at,
+                last_clicked_at,
+                by_variant,
+            } = service.stats(link.id().clone()).await?;
+
+            let view = StatsView {
+                id: link.id().clone().into_inner().to_string(),
+                short_code: link.short_url().as_str().to_string(),
+                total,
+                first_clicked_at: first_clicked_at.map(format_dt),
+                last_clicked_at: last_clicked_at.map(format_dt),
+                by_variant: by_variant
+                    .into_iter()
+                    .map(|stats| VariantClicksView {
+                        variant: stats.variant,
+                        clicks: stats.clicks,
+                    })
+                    .collect(),
+            };
+
+            match cli.output {
+                Output::Json => println!("{}", serde_json::to_string_pretty(&view)?),
+                Output::Table => {
+                    println!("Link:          {} ({})", view.short_code, view.id);
+                    println!("Total clicks:  {}", view.total);
+                    println!(
+                        "First click:   {}",
+                        view.first_clicked_at.as_deref().unwrap_or("-")
+                    );
+                    println!(
+                        "Last click:    {}",
+                        view.last_clicked_at.as_deref().unwrap_or("-")
+                    );
+                    for variant in &view.by_variant {
+                        println!("  {:<12} {}", variant.variant, variant.clicks);
+                    }
+                }
+            }
+        }
+        Command::Campaigns => {
+            let views: Vec<CampaignView> = service
+                .campaign_stats()
+                .await?
+                .into_iter()
+                .map(|stats| CampaignView {
+                    campaign: stats.campaign,
+                    links: stats.links,
+                    clicks: stats.clicks,
+                    last_clicked_at: stats.last_clicked_at.map(format_dt),
+                })
+                .collect();
+
+            match cli.output {
+                Output::Json => println!("{}", serde_json::to_string_pretty(&views)?),
+                Output::Table => {
+                    println!(
+                        "{:<32}  {:>6}  {:>8}  LAST CLICK",
+                        "CAMPAIGN", "LINKS", "CLICKS"
+                    );
+
+                    for view in views {
+                        println!(
+                            "{:<32}  {:>6}  {:>8}  {}",
+                            view.campaign,
+                            view.links,
+                            view.clicks,
+                            view.last_clicked_at.as_deref().unwrap_or("-")
+                        );
+                    }
+                }
+            }
+        }
+        Command::Export { format, clicks, to } => {
+            let mut writer: Box<dyn Write> = match to {
+                Some(path) => Box::new(std::fs::File::create(path)?),
+                None => Box::new(std::io::stdout().lock()),
+            };
+
+            let mut offset = 0;
+
+            loop {
+                let entries = service
+                    .export_page(offset, EXPORT_PAGE_SIZE, clicks)
+                    .await?;
+
+                writer.write_all(&encode_entries(&entries, format.into(), offset == 0)?)?;
+
+                if (entries.len() as i64) < EXPORT_PAGE_SIZE {
+                    break;
+                }
+
+                offset += EXPORT_PAGE_SIZE;
+            }
+
+            writer.flush()?;
+        }
+        Command::Import {
+            from,
+            format,
+            dry_run,
+        } => {
+            let mut body = Vec::new();
+
+            if from == "-" {
+                std::io::stdin().read_to_end(&mut body)?;
+            } else {
+                body = std::fs::read(from)?;
+            }
+
+            let mut entries = Vec::new();
+            let slots = decode_entries(&body, format.into())
+                .into_iter()
+                .map(|row| row.map(|entry| entries.push(entry)))
+                .collect();
+
+            let outcomes = service.import(entries, dry_run).await?;
+            let report = ImportReport::new(dry_run, slots, outcomes);
+
+            match cli.output {
+                Output::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+                Output::Table => {
+                    for row in &report.rows {
+                        println!(
+                            "{:>6}  {:<12}  {}",
+                            row.row,
+                            row.status,
+                            row.detail.as_deref().unwrap_or("")
+                        );
+                    }
+
+                    println!(
+                        "{} {}, {} conflicts, {} failed",
+                        report.imported,
+                        if dry_run { "would import" } else { "imported" },
+                        report.conflicts,
+                        report.failed
+                    );
+                }
+            }
+        }
+        Command::PurgeExpired => {
+            let purged = service.purge_expired().await?;
+            println!("Purged {purged} expired links");
+        }
+        Command::Migrate => unreachable!("handled before the service is built"),
+    }
+
+    Ok(())
+}
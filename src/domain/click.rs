@@ -0,0 +1,68 @@
+use crate::domain::link::LinkId;
+use chrono::{DateTime, NaiveDate, Utc};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Click {
+    link_id: LinkId,
+    clicked_at: DateTime<Utc>,
+    // The A/B variant the visitor was sent to, if the link is split.
+    variant: Option<String>,
+}
+
+impl Click {
+    pub fn new(link_id: LinkId, clicked_at: DateTime<Utc>) -> Self {
+        Self {
+            link_id,
+            clicked_at,
+            variant: None,
+        }
+    }
+
+    pub fn with_variant(mut self, variant: Option<String>) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    pub fn link_id(&self) -> &LinkId {
+        &self.link_id
+    }
+
+    pub fn clicked_at(&self) -> DateTime<Utc> {
+        self.clicked_at
+    }
+
+    pub fn variant(&self) -> Option<&str> {
+        self.variant.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClickStats {
+    pub total: i64,
+    pub first_clicked_at: Option<DateTime<Utc>>,
+    pub last_clicked_at: Option<DateTime<Utc>>,
+    // Per-variant totals for split links, ordered by variant name.
+    pub by_variant: Vec<VariantClicks>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantClicks {
+    pub variant: String,
+    pub clicks: i64,
+}
+
+// One point of the per-day click chart; days are UTC.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyClicks {
+    pub day: NaiveDate,
+    pub clicks: i64,
+}
+
+// Clicks rolled up over every link tagged with the same `utm_campaign`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CampaignStats {
+    pub campaign: String,
+    pub links: i64,
+    pub clicks: i64,
+    pub last_clicked_at: Option<DateTime<Utc>>,
+}
@@ -1,10 +1,75 @@
+use chrono::{DateTime, Utc};
 use thiserror::Error;
 
-#[derive(Debug, Error)]
-pub enum LinkError {
-    #[error("Any Link must have an id")]
-    LinkIdNotFound,
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum UrlError {
+    #[error("Enter a URL to shorten")]
+    Empty,
+
+    #[error("The URL is longer than {max} characters")]
+    TooLong { max: usize },
+
+    #[error("That is not a valid URL; include the scheme, e.g. https://example.com")]
+    Unparsable,
+
+    #[error("Only http and https links can be shortened, not '{0}:'")]
+    BadScheme(String),
+
+    #[error("The URL must include a host name")]
+    MissingHost,
+
+    #[error("The host name could not be resolved")]
+    Unresolvable,
+
+    #[error("The URL points to a private or reserved network address")]
+    PrivateAddress,
+
+    #[error("Links to this domain are not allowed")]
+    BlockedDomain,
+}
+
+impl UrlError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            UrlError::Empty => "url_empty",
+            UrlError::TooLong { .. } => "url_too_long",
+            UrlError::Unparsable => "url_unparsable",
+            UrlError::BadScheme(_) => "url_bad_scheme",
+            UrlError::MissingHost => "url_missing_host",
+            UrlError::Unresolvable => "url_unresolvable",
+            UrlError::PrivateAddress => "url_private_address",
+            UrlError::BlockedDomain => "url_blocked_domain",
+        }
+    }
+}
+
+// What went wrong, as far as a caller is concerned. All but the last two
+// are about the request and are safe to explain; those two are failures of
+// the service itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    InvalidInput,
+    // No credentials, or ones that match nobody.
+    Unauthorized,
+    TooLarge,
+    UnsupportedMediaType,
+    // The request was not read and answered within the time allowed.
+    TimedOut,
+    // The caller has to slow down before trying again.
+    TooManyRequests,
+    NotFound,
+    Conflict,
+    Forbidden,
+    // The link exists but no longer resolves.
+    Gone,
+    // Overloaded or unreachable infrastructure; worth retrying later.
+    Unavailable,
+    // A bug or an unexpected infrastructure failure.
+    Internal,
+}
 
+#[derive(Debug, Clone, Error)]
+pub enum LinkError {
     #[error("Link hashed code not empty")]
     EmptyHashedCode,
 
@@ -14,21 +79,263 @@ pub enum LinkError {
     #[error("User url is empty")]
     EmptyURL,
 
-    #[error("Invalid Url")]
-    InvalidUrl,
+    #[error(transparent)]
+    InvalidUrl(#[from] UrlError),
 
+    // Unexpected storage failures. The text is for the log only.
     #[error("Persistence error: {0}")]
     PersistenceError(String),
 
+    // The database is overloaded or unreachable; worth retrying later.
+    #[error("The service is temporarily unavailable")]
+    Unavailable,
+
     #[error("Link creation error")]
     LinkCreationError,
 
-    #[error("Hashed code mismatch")]
+    #[error("The delete key does not match this link")]
     HashedCodeMismatch,
 
+    // Guessing is cut off per link; `retry_after` is in seconds.
+    #[error("Too many wrong delete keys for this link; try again later")]
+    TooManyAttempts { retry_after: u64 },
+
     #[error("Not found")]
     NotFound,
 
     #[error("Invalid format")]
     InvalidFormat,
+
+    #[error("Alias must be 3-10 letters, digits, '-' or '_'")]
+    InvalidAlias,
+
+    #[error("Alias is already taken")]
+    AliasTaken,
+
+    #[error("Tags must be 1-32 characters")]
+    InvalidTag,
+
+    #[error("Expiry must be a future RFC 3339 timestamp")]
+    InvalidExpiry,
+
+    #[error("Activation time must be an RFC 3339 timestamp before the expiry")]
+    InvalidSchedule,
+
+    #[error("Redirect status must be 301, 302, 307 or 308")]
+    InvalidRedirect,
+
+    #[error("UTM fields must be at most 128 characters")]
+    InvalidUtm,
+
+    #[error("Invalid routing rule: {0}")]
+    InvalidRule(String),
+
+    #[error("Invalid variant: {0}")]
+    InvalidVariant(String),
+
+    #[error(
+        "Path may not start with 'qr' or 'report', which are reserved, or contain dot \
+         segments, backslashes or encoded slashes"
+    )]
+    InvalidPath,
+
+    #[error("Link has expired")]
+    Expired,
+
+    #[error("Link is not active until {0}")]
+    NotYetActive(DateTime<Utc>),
+
+    #[error("Batch aborted by a failing row")]
+    BatchAborted,
+
+    #[error("Link has been disabled")]
+    Disabled(Option<String>),
+
+    #[error("Link has been blocked")]
+    Blocked(Option<String>),
+
+    #[error("Invalid report: {0}")]
+    InvalidReport(String),
+
+    #[error("Status reason must be at most 500 characters")]
+    InvalidReason,
+
+    #[error("A {from} link cannot be made {to}")]
+    InvalidTransition {
+        from: &'static str,
+        to: &'static str,
+    },
+
+    #[error("Destination is on the threat list ({0})")]
+    ThreatDetected(String),
+
+    // Request problems outside the link model: a malformed body, query or
+    // form. The text is shown to the caller.
+    #[error("{0}")]
+    InvalidInput(String),
+
+    #[error("{0}")]
+    Unauthorized(String),
+
+    #[error("{0}")]
+    PayloadTooLarge(String),
+
+    #[error("{0}")]
+    UnsupportedMediaType(String),
+
+    #[error("The request took too long")]
+    TimedOut,
+
+    #[error("The form could not be verified. Reload the page and try again.")]
+    FormRejected,
+
+    // The lists file is the operator's, so its parse error is shown as is.
+    #[error("Invalid list file: {0}")]
+    InvalidList(String),
+
+    // A response could not be put together, e.g. a stored destination that
+    // is not a valid header value. The text is for the log only.
+    #[error("Could not build the response: {0}")]
+    ResponseFailure(String),
+}
+
+impl LinkError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            LinkError::EmptyURL
+            | LinkError::InvalidFormat
+            | LinkError::InvalidAlias
+            | LinkError::InvalidTag
+            | LinkError::InvalidExpiry
+            | LinkError::InvalidSchedule
+            | LinkError::InvalidRedirect
+            | LinkError::InvalidUtm
+            | LinkError::InvalidRule(_)
+            | LinkError::InvalidVariant(_)
+            | LinkError::InvalidPath
+            | LinkError::InvalidReport(_)
+            | LinkError::InvalidReason
+            | LinkError::InvalidInput(_)
+            | LinkError::InvalidList(_)
+            | LinkError::BatchAborted => ErrorKind::InvalidInput,
+
+            LinkError::Unauthorized(_) => ErrorKind::Unauthorized,
+            LinkError::PayloadTooLarge(_) => ErrorKind::TooLarge,
+            LinkError::UnsupportedMediaType(_) => ErrorKind::UnsupportedMediaType,
+            LinkError::TimedOut => ErrorKind::TimedOut,
+            LinkError::TooManyAttempts { .. } => ErrorKind::TooManyRequests,
+
+            LinkError::InvalidUrl(UrlError::PrivateAddress | UrlError::BlockedDomain) => {
+                ErrorKind::Forbidden
+            }
+            LinkError::InvalidUrl(_) => ErrorKind::InvalidInput,
+
+            // A link that is not live yet looks missing to a visitor.
+            LinkError::NotFound | LinkError::NotYetActive(_) => ErrorKind::NotFound,
+
+            LinkError::AliasTaken | LinkError::InvalidTransition { .. } => ErrorKind::Conflict,
+
+            LinkError::HashedCodeMismatch
+            | LinkError::ThreatDetected(_)
+            | LinkError::FormRejected => ErrorKind::Forbidden,
+
+            LinkError::Expired | LinkError::Disabled(_) | LinkError::Blocked(_) => ErrorKind::Gone,
+
+            LinkError::Unavailable => ErrorKind::Unavailable,
+
+            LinkError::EmptyHashedCode
+            | LinkError::CodeGenerationFailure
+            | LinkError::PersistenceError(_)
+            | LinkError::ResponseFailure(_)
+            | LinkError::LinkCreationError => ErrorKind::Internal,
+        }
+    }
+
+    // Stable identifiers for API clients; messages may be reworded, these
+    // may not.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LinkError::EmptyURL => "url_empty",
+            LinkError::InvalidUrl(e) => e.code(),
+            LinkError::InvalidFormat => "invalid_format",
+            LinkError::InvalidAlias => "invalid_alias",
+            LinkError::InvalidTag => "invalid_tag",
+            LinkError::InvalidExpiry => "invalid_expiry",
+            LinkError::InvalidSchedule => "invalid_schedule",
+            LinkError::InvalidRedirect => "invalid_redirect",
+            LinkError::InvalidUtm => "invalid_utm",
+            LinkError::InvalidRule(_) => "invalid_rule",
+            LinkError::InvalidVariant(_) => "invalid_variant",
+            LinkError::InvalidPath => "invalid_path",
+            LinkError::InvalidReport(_) => "invalid_report",
+            LinkError::InvalidReason => "invalid_reason",
+            LinkError::BatchAborted => "batch_aborted",
+            LinkError::InvalidInput(_) => "invalid_input",
+            LinkError::InvalidList(_) => "invalid_list",
+            LinkError::Unauthorized(_) => "unauthorized",
+            LinkError::PayloadTooLarge(_) => "payload_too_large",
+            LinkError::UnsupportedMediaType(_) => "unsupported_media_type",
+            LinkError::FormRejected => "form_rejected",
+            LinkError::TimedOut => "timed_out",
+            LinkError::NotFound => "not_found",
+            LinkError::NotYetActive(_) => "not_yet_active",
+            LinkError::AliasTaken => "alias_taken",
+            LinkError::InvalidTransition { .. } => "invalid_transition",
+            LinkError::HashedCodeMismatch => "delete_key_mismatch",
+            LinkError::TooManyAttempts { .. } => "too_many_attempts",
+            LinkError::ThreatDetected(_) => "threat_detected",
+            LinkError::Expired => "expired",
+            LinkError::Disabled(_) => "disabled",
+            LinkError::Blocked(_) => "blocked",
+            LinkError::Unavailable => "unavailable",
+            LinkError::EmptyHashedCode
+            | LinkError::CodeGenerationFailure
+            | LinkError::PersistenceError(_)
+            | LinkError::ResponseFailure(_)
+            | LinkError::LinkCreationError => "internal",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_failures_are_internal() {
+        let error = LinkError::PersistenceError("connection reset".into());
+
+        assert_eq!(error.kind(), ErrorKind::Internal);
+        assert_eq!(error.code(), "internal");
+        assert_eq!(LinkError::Unavailable.kind(), ErrorKind::Unavailable);
+    }
+
+    #[test]
+    fn url_errors_keep_their_own_codes() {
+        let blocked = LinkError::InvalidUrl(UrlError::BlockedDomain);
+        let too_long = LinkError::InvalidUrl(UrlError::TooLong { max: 2048 });
+
+        assert_eq!(blocked.kind(), ErrorKind::Forbidden);
+        assert_eq!(blocked.code(), "url_blocked_domain");
+        assert_eq!(too_long.kind(), ErrorKind::InvalidInput);
+        assert_eq!(too_long.code(), "url_too_long");
+    }
+
+    #[test]
+    fn request_errors_have_their_own_kinds() {
+        let unauthorized = LinkError::Unauthorized("Invalid API key".into());
+        let too_large = LinkError::PayloadTooLarge("too many rows".into());
+
+        assert_eq!(unauthorized.kind(), ErrorKind::Unauthorized);
+        assert_eq!(unauthorized.code(), "unauthorized");
+        assert_eq!(too_large.kind(), ErrorKind::TooLarge);
+        assert_eq!(
+            LinkError::ResponseFailure("bad header".into()).kind(),
+            ErrorKind::Internal
+        );
+
+        let locked_out = LinkError::TooManyAttempts { retry_after: 60 };
+        assert_eq!(locked_out.kind(), ErrorKind::TooManyRequests);
+        assert_eq!(locked_out.code(), "too_many_attempts");
+    }
 }
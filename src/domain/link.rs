@@ -1,9 +1,11 @@
-use crate::domain::errors::LinkError;
+use crate::domain::errors::{LinkError, UrlError};
+use crate::domain::routing::{choose_variant, find_rule, Audience, RoutingRule, Variant};
 use chrono::{DateTime, Utc};
 use hex;
 use rand::RngCore;
 use rand::{rngs::OsRng, Rng};
 use sha2::{Digest, Sha256};
+use url::Url as ExternalUrl;
 use uuid::Uuid;
 
 // OWASP A01 Broken Access Control
@@ -36,6 +38,13 @@ impl From<Uuid> for LinkId {
 #[derive(Debug, Clone, PartialEq)]
 pub struct LinkKey(String);
 
+// OWASP A07 Identification and Authentication Failures: keys are compared
+// as digests so the comparison time does not depend on how much of a
+// guess was right.
+pub fn key_matches(presented: &str, expected: &str) -> bool {
+    Sha256::digest(presented.as_bytes()) == Sha256::digest(expected.as_bytes())
+}
+
 impl LinkKey {
     pub fn generate() -> Result<Self, LinkError> {
         let mut random_bytes = [0u8; 16];
@@ -110,6 +119,26 @@ impl ShortUrl {
         Ok(Self(char_merged_trim.to_string()))
     }
 
+    // Custom aliases share the short_code column, so they obey its width.
+    pub fn alias(raw: &str) -> Result<Self, LinkError> {
+        let alias = raw.trim();
+
+        let valid_length = (3..=10).contains(&alias.len());
+        let valid_chars = alias
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        if !valid_length || !valid_chars {
+            return Err(LinkError::InvalidAlias);
+        }
+
+        Ok(Self(alias.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
     pub fn into_inner(self) -> String {
         self.0
     }
@@ -129,6 +158,11 @@ impl TryFrom<String> for ShortUrl {
     }
 }
 
+// Browsers and proxies start truncating or refusing URLs not far past this,
+// and every rewrite of a destination (canonical form, UTM fields,
+// passthrough) is held to it.
+pub const MAX_URL_LENGTH: usize = 2048;
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct UserUrl {
     raw: String,
@@ -149,6 +183,276 @@ impl UserUrl {
     pub fn as_str(&self) -> &str {
         &self.raw
     }
+
+    fn bounded(raw: String) -> Result<Self, LinkError> {
+        if raw.len() > MAX_URL_LENGTH {
+            return Err(UrlError::TooLong {
+                max: MAX_URL_LENGTH,
+            }
+            .into());
+        }
+
+        Ok(Self { raw })
+    }
+
+    // Two spellings of the same destination should compare equal. Parsing
+    // already lowercases the host, applies IDNA, drops default ports and
+    // resolves dot segments; on top of that percent-escapes are uppercased,
+    // an empty query is dropped and, optionally, tracking parameters go.
+    pub fn canonical(raw: &str, strip_tracking: bool) -> Result<Self, LinkError> {
+        let mut parsed = ExternalUrl::parse(raw.trim()).map_err(|_| UrlError::Unparsable)?;
+
+        if let Some(host) = parsed.host_str() {
+            if host.ends_with('.') {
+                let host = host.trim_end_matches('.').to_string();
+                parsed
+                    .set_host(Some(&host))
+                    .map_err(|_| UrlError::Unparsable)?;
+            }
+        }
+
+        let path = uppercase_escapes(parsed.path());
+        parsed.set_path(&path);
+
+        // Kept parameters retain their original order and spelling.
+        let query: Vec<String> = parsed
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .filter(|pair| {
+                let key = pair.split('=').next().unwrap_or_default();
+                !(strip_tracking && is_tracking_param(key))
+            })
+            .map(uppercase_escapes)
+            .collect();
+
+        if query.is_empty() {
+            parsed.set_query(None);
+        } else {
+            parsed.set_query(Some(&query.join("&")));
+        }
+
+        Self::bounded(parsed.to_string())
+    }
+
+    // `extra_path` and `query` arrive exactly as they were on the request
+    // line, so existing escapes are kept and only characters that are not
+    // allowed in a URL get encoded.
+    //
+    // `/l/:code/qr` and `/l/:code/report` are routes of their own, so a path
+    // starting with either segment is refused rather than passed through
+    // only some of the time.
+    pub fn with_passthrough(
+        &self,
+        extra_path: Option<&str>,
+        query: Option<&str>,
+        conflict: QueryConflict,
+    ) -> Result<Self, LinkError> {
+        let mut parsed = ExternalUrl::parse(&self.raw).map_err(|_| UrlError::Unparsable)?;
+
+        if let Some(extra) = extra_path.filter(|extra| !extra.is_empty()) {
+            // Dot segments would let a visitor climb out of the destination's
+            // path onto other pages of the same site. Backslashes and escaped
+            // separators are refused too, since servers may read them as `/`.
+            let lowered = extra.to_ascii_lowercase();
+            let smuggled =
+                lowered.contains('\\') || lowered.contains("%5c") || lowered.contains("%2f");
+            let climbs = lowered.split('/').any(|segment| {
+                let segment = segment.replace("%2e", ".");
+                segment == "." || segment == ".."
+            });
+
+            let reserved = extra
+                .trim_start_matches('/')
+                .split('/')
+                .next()
+                .is_some_and(|first| RESERVED_PASSTHROUGH_SEGMENTS.contains(&first));
+
+            if smuggled || climbs || reserved {
+                return Err(LinkError::InvalidPath);
+            }
+
+            let base = format!("{}/", parsed.path().trim_end_matches('/'));
+            parsed.set_path(&format!("{base}{}", extra.trim_start_matches('/')));
+
+            // Whatever the parser normalised, the result has to stay below
+            // the destination's own path.
+            if !parsed.path().starts_with(&base) {
+                return Err(LinkError::InvalidPath);
+            }
+        }
+
+        let incoming: Vec<&str> = query
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .collect();
+
+        if !incoming.is_empty() {
+            let existing = parsed.query().unwrap_or_default().to_string();
+            let existing: Vec<&str> = existing.split('&').filter(|p| !p.is_empty()).collect();
+
+            let key = |pair: &&str| pair.split('=').next().unwrap_or_default().to_string();
+            let incoming_keys: Vec<String> = incoming.iter().map(key).collect();
+            let existing_keys: Vec<String> = existing.iter().map(key).collect();
+
+            let merged: Vec<&str> = match conflict {
+                QueryConflict::KeepDestination => existing
+                    .iter()
+                    .copied()
+                    .chain(
+                        incoming
+                            .iter()
+                            .copied()
+                            .filter(|pair| !existing_keys.contains(&key(pair))),
+                    )
+                    .collect(),
+                QueryConflict::PreferIncoming => existing
+                    .iter()
+                    .copied()
+                    .filter(|pair| !incoming_keys.contains(&key(pair)))
+                    .chain(incoming.iter().copied())
+                    .collect(),
+                QueryConflict::Append => existing.iter().chain(incoming.iter()).copied().collect(),
+            };
+
+            parsed.set_query(Some(&merged.join("&")));
+        }
+
+        Self::bounded(parsed.to_string())
+    }
+}
+
+// Kept in step with the routes under `/l/:code` and `LinkError::InvalidPath`.
+const RESERVED_PASSTHROUGH_SEGMENTS: [&str; 2] = ["qr", "report"];
+
+const MAX_UTM_LENGTH: usize = 128;
+
+// Campaign fields entered separately from the destination. They are merged
+// into the URL as `utm_*` parameters and kept on the link for reporting.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Utm {
+    pub source: Option<String>,
+    pub medium: Option<String>,
+    pub campaign: Option<String>,
+    pub term: Option<String>,
+    pub content: Option<String>,
+}
+
+impl Utm {
+    // Blank fields are dropped; anything left must fit in a reasonable cell.
+    pub fn normalised(self) -> Result<Self, LinkError> {
+        let clean = |value: Option<String>| -> Result<Option<String>, LinkError> {
+            match value.map(|v| v.trim().to_string()) {
+                Some(v) if v.is_empty() => Ok(None),
+                Some(v) if v.chars().count() > MAX_UTM_LENGTH => Err(LinkError::InvalidUtm),
+                other => Ok(other),
+            }
+        };
+
+        Ok(Self {
+            source: clean(self.source)?,
+            medium: clean(self.medium)?,
+            campaign: clean(self.campaign)?,
+            term: clean(self.term)?,
+            content: clean(self.content)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs().next().is_none()
+    }
+
+    fn pairs(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [
+            ("utm_source", &self.source),
+            ("utm_medium", &self.medium),
+            ("utm_campaign", &self.campaign),
+            ("utm_term", &self.term),
+            ("utm_content", &self.content),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.as_deref().map(|value| (key, value)))
+    }
+}
+
+impl UserUrl {
+    // Structured fields replace any `utm_*` parameter of the same name that
+    // was typed into the URL by hand.
+    pub fn with_utm(&self, utm: &Utm) -> Result<Self, LinkError> {
+        if utm.is_empty() {
+            return Ok(self.clone());
+        }
+
+        let mut parsed = ExternalUrl::parse(&self.raw).map_err(|_| UrlError::Unparsable)?;
+
+        let kept: Vec<(String, String)> = parsed
+            .query_pairs()
+            .filter(|(key, _)| utm.pairs().all(|(utm_key, _)| key != utm_key))
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+
+        parsed
+            .query_pairs_mut()
+            .clear()
+            .extend_pairs(kept)
+            .extend_pairs(utm.pairs());
+
+        Self::bounded(parsed.to_string())
+    }
+}
+
+// What happens when a passed-through parameter is already set on the
+// destination.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueryConflict {
+    #[default]
+    KeepDestination,
+    PreferIncoming,
+    Append,
+}
+
+impl std::str::FromStr for QueryConflict {
+    type Err = LinkError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "destination" => Ok(QueryConflict::KeepDestination),
+            "incoming" => Ok(QueryConflict::PreferIncoming),
+            "append" => Ok(QueryConflict::Append),
+            _ => Err(LinkError::InvalidFormat),
+        }
+    }
+}
+
+const TRACKING_PARAMS: [&str; 11] = [
+    "fbclid", "gclid", "dclid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "_ga", "_gl",
+    "ref_src",
+];
+
+fn is_tracking_param(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.starts_with("utm_") || TRACKING_PARAMS.contains(&key.as_str())
+}
+
+fn uppercase_escapes(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        out.push(c);
+
+        if c == '%' {
+            for _ in 0..2 {
+                if let Some(hex) = chars.next() {
+                    out.push(hex.to_ascii_uppercase());
+                }
+            }
+        }
+    }
+
+    out
 }
 
 impl TryFrom<String> for UserUrl {
@@ -186,6 +490,187 @@ impl From<DateTime<Utc>> for CreatedAt {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpiresAt(DateTime<Utc>);
+
+impl ExpiresAt {
+    pub fn new(value: DateTime<Utc>, created_at: DateTime<Utc>) -> Result<Self, LinkError> {
+        if value <= created_at {
+            return Err(LinkError::InvalidExpiry);
+        }
+
+        Ok(Self(value))
+    }
+
+    pub fn has_passed(&self, now: DateTime<Utc>) -> bool {
+        self.0 <= now
+    }
+
+    pub fn into_inner(self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+impl From<DateTime<Utc>> for ExpiresAt {
+    fn from(value: DateTime<Utc>) -> Self {
+        ExpiresAt(value)
+    }
+}
+
+// Launch time of a scheduled link. A time already in the past is allowed and
+// simply means the link is live straight away.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveFrom(DateTime<Utc>);
+
+impl ActiveFrom {
+    pub fn new(value: DateTime<Utc>, expires_at: Option<&ExpiresAt>) -> Result<Self, LinkError> {
+        if expires_at.is_some_and(|expires_at| expires_at.0 <= value) {
+            return Err(LinkError::InvalidSchedule);
+        }
+
+        Ok(Self(value))
+    }
+
+    pub fn has_arrived(&self, now: DateTime<Utc>) -> bool {
+        self.0 <= now
+    }
+
+    pub fn into_inner(self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+impl From<DateTime<Utc>> for ActiveFrom {
+    fn from(value: DateTime<Utc>) -> Self {
+        ActiveFrom(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag(String);
+
+impl Tag {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl TryFrom<String> for Tag {
+    type Error = LinkError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let value_trimmed = value.trim();
+
+        if value_trimmed.is_empty() || value_trimmed.len() > 32 {
+            return Err(LinkError::InvalidTag);
+        }
+
+        Ok(Self(value_trimmed.to_lowercase()))
+    }
+}
+
+// `Disabled` is a reversible pause; `Blocked` is an admin takedown, e.g. after
+// an abuse report, and can only be lifted back to `Active` deliberately.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkStatus {
+    #[default]
+    Active,
+    Disabled,
+    Blocked,
+}
+
+impl LinkStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkStatus::Active => "active",
+            LinkStatus::Disabled => "disabled",
+            LinkStatus::Blocked => "blocked",
+        }
+    }
+
+    pub fn can_become(self, next: LinkStatus) -> bool {
+        matches!(
+            (self, next),
+            (
+                LinkStatus::Active,
+                LinkStatus::Disabled | LinkStatus::Blocked
+            ) | (
+                LinkStatus::Disabled,
+                LinkStatus::Active | LinkStatus::Blocked
+            ) | (LinkStatus::Blocked, LinkStatus::Active)
+        )
+    }
+}
+
+impl TryFrom<&str> for LinkStatus {
+    type Error = LinkError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim() {
+            "active" => Ok(LinkStatus::Active),
+            "disabled" => Ok(LinkStatus::Disabled),
+            "blocked" => Ok(LinkStatus::Blocked),
+            _ => Err(LinkError::InvalidFormat),
+        }
+    }
+}
+
+// 301/308 may be cached indefinitely by browsers and crawlers, so later
+// edits, disables and click counts only apply to visitors that come back
+// to the server; 302/307 are re-requested every time. 307/308 additionally
+// keep the request method and body.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RedirectKind {
+    MovedPermanently,
+    #[default]
+    Found,
+    TemporaryRedirect,
+    PermanentRedirect,
+}
+
+impl RedirectKind {
+    pub fn status_code(&self) -> u16 {
+        match self {
+            RedirectKind::MovedPermanently => 301,
+            RedirectKind::Found => 302,
+            RedirectKind::TemporaryRedirect => 307,
+            RedirectKind::PermanentRedirect => 308,
+        }
+    }
+
+    pub fn is_permanent(&self) -> bool {
+        matches!(
+            self,
+            RedirectKind::MovedPermanently | RedirectKind::PermanentRedirect
+        )
+    }
+
+    pub fn preserves_method(&self) -> bool {
+        matches!(
+            self,
+            RedirectKind::TemporaryRedirect | RedirectKind::PermanentRedirect
+        )
+    }
+}
+
+impl TryFrom<u16> for RedirectKind {
+    type Error = LinkError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            301 => Ok(RedirectKind::MovedPermanently),
+            302 => Ok(RedirectKind::Found),
+            307 => Ok(RedirectKind::TemporaryRedirect),
+            308 => Ok(RedirectKind::PermanentRedirect),
+            _ => Err(LinkError::InvalidRedirect),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Link {
     id: LinkId,
@@ -193,6 +678,25 @@ pub struct Link {
     short_url: ShortUrl,
     user_url: UserUrl,
     created_at: CreatedAt,
+    // Before this the link resolves to the "coming soon" response.
+    active_from: Option<ActiveFrom>,
+    expires_at: Option<ExpiresAt>,
+    tags: Vec<Tag>,
+    status: LinkStatus,
+    // Why the link was last disabled or blocked; cleared on re-activation.
+    status_reason: Option<String>,
+    always_preview: bool,
+    // Set when the link was created with an API key; anonymous links have none.
+    owner: Option<String>,
+    // `None` follows the server's `DEFAULT_REDIRECT_STATUS`.
+    redirect_kind: Option<RedirectKind>,
+    // Forward any path below the short code and the visitor's query string.
+    passthrough: bool,
+    utm: Utm,
+    routing_rules: Vec<RoutingRule>,
+    // When set, visitors not caught by a routing rule are split between
+    // these instead of going to `user_url`.
+    variants: Vec<Variant>,
 }
 
 impl Link {
@@ -215,9 +719,81 @@ impl Link {
             short_url: generated_url,
             user_url: input_url,
             created_at: creation_time,
+            active_from: None,
+            expires_at: None,
+            tags: Vec::new(),
+            status: LinkStatus::Active,
+            status_reason: None,
+            always_preview: false,
+            owner: None,
+            redirect_kind: None,
+            passthrough: false,
+            utm: Utm::default(),
+            routing_rules: Vec::new(),
+            variants: Vec::new(),
         })
     }
 
+    pub fn with_active_from(mut self, active_from: Option<ActiveFrom>) -> Self {
+        self.active_from = active_from;
+        self
+    }
+
+    pub fn with_expires_at(mut self, expires_at: Option<ExpiresAt>) -> Self {
+        self.expires_at = expires_at;
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<Tag>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn with_status(mut self, status: LinkStatus) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_status_reason(mut self, reason: Option<String>) -> Self {
+        self.status_reason = reason;
+        self
+    }
+
+    pub fn with_always_preview(mut self, always_preview: bool) -> Self {
+        self.always_preview = always_preview;
+        self
+    }
+
+    pub fn with_owner(mut self, owner: Option<String>) -> Self {
+        self.owner = owner;
+        self
+    }
+
+    pub fn with_redirect_kind(mut self, redirect_kind: Option<RedirectKind>) -> Self {
+        self.redirect_kind = redirect_kind;
+        self
+    }
+
+    pub fn with_passthrough(mut self, passthrough: bool) -> Self {
+        self.passthrough = passthrough;
+        self
+    }
+
+    pub fn with_utm(mut self, utm: Utm) -> Self {
+        self.utm = utm;
+        self
+    }
+
+    pub fn with_routing_rules(mut self, routing_rules: Vec<RoutingRule>) -> Self {
+        self.routing_rules = routing_rules;
+        self
+    }
+
+    pub fn with_variants(mut self, variants: Vec<Variant>) -> Self {
+        self.variants = variants;
+        self
+    }
+
     pub fn id(&self) -> &LinkId {
         &self.id
     }
@@ -237,4 +813,231 @@ impl Link {
     pub fn created_at(self) -> CreatedAt {
         self.created_at
     }
+
+    pub fn active_from(&self) -> Option<&ActiveFrom> {
+        self.active_from.as_ref()
+    }
+
+    pub fn expires_at(&self) -> Option<&ExpiresAt> {
+        self.expires_at.as_ref()
+    }
+
+    pub fn tags(&self) -> &[Tag] {
+        &self.tags
+    }
+
+    pub fn status(&self) -> LinkStatus {
+        self.status
+    }
+
+    pub fn status_reason(&self) -> Option<&str> {
+        self.status_reason.as_deref()
+    }
+
+    pub fn always_preview(&self) -> bool {
+        self.always_preview
+    }
+
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    pub fn redirect_kind(&self) -> Option<RedirectKind> {
+        self.redirect_kind
+    }
+
+    pub fn passthrough(&self) -> bool {
+        self.passthrough
+    }
+
+    pub fn utm(&self) -> &Utm {
+        &self.utm
+    }
+
+    pub fn routing_rules(&self) -> &[RoutingRule] {
+        &self.routing_rules
+    }
+
+    pub fn variants(&self) -> &[Variant] {
+        &self.variants
+    }
+
+    // The destination for this visitor before any passthrough is applied,
+    // and the variant it came from when the split decided. `roll` is drawn
+    // from `0..total_weight(self.variants())`.
+    pub fn destination_for(
+        &self,
+        audience: &Audience,
+        sticky_variant: Option<&str>,
+        roll: u32,
+    ) -> (&UserUrl, Option<&Variant>) {
+        if let Some(rule) = find_rule(&self.routing_rules, audience) {
+            return (rule.destination(), None);
+        }
+
+        match choose_variant(&self.variants, sticky_variant, roll) {
+            Some(variant) => (variant.destination(), Some(variant)),
+            None => (&self.user_url, None),
+        }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at
+            .as_ref()
+            .is_some_and(|expires_at| expires_at.has_passed(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_urls_compare_equal() {
+        let canonical = |raw: &str| UserUrl::canonical(raw, false).unwrap().into_inner();
+
+        assert_eq!(
+            canonical("HTTPS://Example.COM.:443/a/./b/../c?x=%2f"),
+            "https://example.com/a/c?x=%2F"
+        );
+        assert_eq!(canonical("http://example.com:80"), "http://example.com/");
+        assert_eq!(
+            canonical("https://bücher.example/?"),
+            "https://xn--bcher-kva.example/"
+        );
+        assert_eq!(
+            canonical("https://example.com/a?b=1&utm_source=x"),
+            "https://example.com/a?b=1&utm_source=x"
+        );
+    }
+
+    #[test]
+    fn passthrough_appends_path_and_merges_query() {
+        let url = UserUrl::new("https://example.com/docs/?lang=en&v=1".to_string());
+        let pass = |path, query, conflict| {
+            url.with_passthrough(path, query, conflict)
+                .map(UserUrl::into_inner)
+        };
+
+        assert_eq!(
+            pass(
+                Some("a%20b/c d"),
+                Some("v=2&x=%2F"),
+                QueryConflict::KeepDestination
+            )
+            .unwrap(),
+            "https://example.com/docs/a%20b/c%20d?lang=en&v=1&x=%2F"
+        );
+        assert_eq!(
+            pass(None, Some("v=2"), QueryConflict::PreferIncoming).unwrap(),
+            "https://example.com/docs/?lang=en&v=2"
+        );
+        assert_eq!(
+            pass(None, Some("v=2"), QueryConflict::Append).unwrap(),
+            "https://example.com/docs/?lang=en&v=1&v=2"
+        );
+        assert_eq!(
+            pass(Some("qrcodes/reports"), None, QueryConflict::Append).unwrap(),
+            "https://example.com/docs/qrcodes/reports?lang=en&v=1"
+        );
+        for climb in [
+            "a/%2E%2E/admin",
+            "..\\admin",
+            "a/..%5Cadmin",
+            "..%2Fadmin",
+            "qr",
+            "report/spam",
+            "/qr/",
+        ] {
+            assert!(
+                matches!(
+                    pass(Some(climb), None, QueryConflict::Append),
+                    Err(LinkError::InvalidPath)
+                ),
+                "{climb}"
+            );
+        }
+    }
+
+    #[test]
+    fn utm_fields_replace_hand_typed_parameters() {
+        let utm = Utm {
+            source: Some("news letter".to_string()),
+            campaign: Some("spring".to_string()),
+            medium: Some("  ".to_string()),
+            ..Utm::default()
+        }
+        .normalised()
+        .unwrap();
+
+        let url = UserUrl::new("https://example.com/a?b=1&utm_source=x&utm_medium=y".to_string());
+
+        assert_eq!(
+            url.with_utm(&utm).unwrap().into_inner(),
+            "https://example.com/a?b=1&utm_medium=y&utm_source=news+letter&utm_campaign=spring"
+        );
+    }
+
+    #[test]
+    fn rewrites_cannot_push_a_destination_past_the_limit() {
+        let path = "a".repeat(MAX_URL_LENGTH - "https://example.com/".len());
+        let url = UserUrl::canonical(&format!("https://example.com/{path}"), false).unwrap();
+
+        let utm = Utm {
+            source: Some("newsletter".to_string()),
+            ..Utm::default()
+        };
+
+        assert!(matches!(
+            url.with_utm(&utm),
+            Err(LinkError::InvalidUrl(UrlError::TooLong { .. }))
+        ));
+        assert!(matches!(
+            url.with_passthrough(None, Some("page=2"), QueryConflict::Append),
+            Err(LinkError::InvalidUrl(UrlError::TooLong { .. }))
+        ));
+    }
+
+    #[test]
+    fn tracking_parameters_are_stripped_on_request() {
+        let canonical = |raw: &str| UserUrl::canonical(raw, true).unwrap().into_inner();
+
+        assert_eq!(
+            canonical("https://Example.com/a?b=1&utm_source=x&fbclid=abc"),
+            canonical("https://example.com/a?b=1")
+        );
+        assert_eq!(
+            canonical("https://example.com/?utm_medium=email"),
+            "https://example.com/"
+        );
+    }
+
+    #[test]
+    fn activation_must_precede_expiry() {
+        let launch = DateTime::parse_from_rfc3339("2025-11-01T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let expiry = ExpiresAt::from(launch + chrono::Duration::days(7));
+
+        let active_from = ActiveFrom::new(launch, Some(&expiry)).unwrap();
+        assert!(!active_from.has_arrived(launch - chrono::Duration::seconds(1)));
+        assert!(active_from.has_arrived(launch));
+
+        assert!(matches!(
+            ActiveFrom::new(expiry.clone().into_inner(), Some(&expiry)),
+            Err(LinkError::InvalidSchedule)
+        ));
+    }
+
+    #[test]
+    fn blocked_links_only_return_to_active() {
+        use LinkStatus::*;
+
+        assert!(Active.can_become(Disabled));
+        assert!(Disabled.can_become(Active));
+        assert!(Disabled.can_become(Blocked));
+        assert!(Blocked.can_become(Active));
+        assert!(!Blocked.can_become(Disabled));
+        assert!(!Active.can_become(Active));
+    }
 }
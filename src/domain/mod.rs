@@ -1,3 +1,6 @@
+pub mod click;
 pub mod errors;
 pub mod link;
 pub mod ports;
+pub mod report;
+pub mod routing;
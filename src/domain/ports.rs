@@ -1,14 +1,47 @@
 use crate::domain::{
+    click::{CampaignStats, Click, ClickStats, DailyClicks},
     errors::LinkError,
-    link::{Link, LinkId, LinkKey, ShortUrl},
+    link::{Link, LinkId, LinkKey, LinkStatus, ShortUrl},
+    report::{Report, ReportState},
 };
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::fmt::Debug;
+use std::io;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchMode {
+    // Every row is written or none is.
+    Atomic,
+    // Rows are written independently; failures do not affect other rows.
+    BestEffort,
+}
 
 #[async_trait]
 pub trait LinkPersistence: Send + Sync {
     async fn delete_by_id(&self, id: LinkId) -> Result<Option<Link>, LinkError>;
     async fn save(&self, link: Link) -> Result<LinkId, LinkError>;
+    async fn save_batch(
+        &self,
+        links: Vec<Link>,
+        mode: BatchMode,
+    ) -> Result<Vec<Result<LinkId, LinkError>>, LinkError>;
+    async fn record_clicks(&self, clicks: Vec<Click>) -> Result<(), LinkError>;
+    // The link and its click history are written together or not at all.
+    async fn save_with_clicks(&self, link: Link, clicks: Vec<Click>) -> Result<LinkId, LinkError>;
+    async fn set_status(
+        &self,
+        id: LinkId,
+        status: LinkStatus,
+        reason: Option<String>,
+    ) -> Result<(), LinkError>;
+    async fn purge_expired(&self, now: DateTime<Utc>) -> Result<u64, LinkError>;
+    // `false` when the same reporter already reported this link.
+    async fn save_report(&self, report: Report) -> Result<bool, LinkError>;
+    async fn set_report_state(&self, id: Uuid, state: ReportState) -> Result<(), LinkError>;
 }
 
 #[async_trait]
@@ -16,4 +49,57 @@ pub trait LinkQuery: Send + Sync {
     async fn find_by_id(&self, id: LinkId) -> Result<Link, LinkError>;
     async fn find_delete_key(&self, id: LinkId) -> Result<LinkKey, LinkError>;
     async fn find_by_short_code(&self, short_code: ShortUrl) -> Result<Link, LinkError>;
+    // `status` narrows the page to links in that state.
+    async fn list(
+        &self,
+        offset: i64,
+        limit: i64,
+        status: Option<LinkStatus>,
+    ) -> Result<Vec<Link>, LinkError>;
+    // Newest first. `term` matches the short code or destination,
+    // case-insensitively.
+    async fn search(
+        &self,
+        term: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Link>, LinkError>;
+    // Only active links are returned; a disabled duplicate is never reused.
+    async fn find_by_owner_and_url(
+        &self,
+        owner: &str,
+        long_url: &str,
+    ) -> Result<Option<Link>, LinkError>;
+    async fn find_clicks(&self, id: LinkId) -> Result<Vec<Click>, LinkError>;
+    async fn click_stats(&self, id: LinkId) -> Result<ClickStats, LinkError>;
+    // Days without clicks are left out.
+    async fn daily_clicks(
+        &self,
+        id: LinkId,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<DailyClicks>, LinkError>;
+    async fn campaign_stats(&self) -> Result<Vec<CampaignStats>, LinkError>;
+    async fn find_report(&self, id: Uuid) -> Result<Report, LinkError>;
+    // Oldest first, so the queue is worked in the order reports arrived.
+    async fn list_reports(
+        &self,
+        state: Option<ReportState>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Report>, LinkError>;
+    async fn count_open_reports(&self, link_id: LinkId) -> Result<i64, LinkError>;
+}
+
+// Resolution is a port so validation never blocks the runtime and can be
+// exercised without a network.
+#[async_trait]
+pub trait HostResolver: Send + Sync + Debug {
+    async fn resolve(&self, host: &str) -> io::Result<Vec<IpAddr>>;
+}
+
+// Country lookups come from a local database file so the redirect path never
+// waits on a remote service.
+pub trait GeoLocator: Send + Sync + Debug {
+    // ISO 3166-1 alpha-2 code, or `None` when the address is not covered.
+    fn country(&self, ip: IpAddr) -> Option<String>;
 }
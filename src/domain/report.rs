@@ -0,0 +1,228 @@
+use crate::domain::errors::LinkError;
+use crate::domain::link::LinkId;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, Ipv6Addr};
+use uuid::Uuid;
+
+pub const MAX_REPORT_DETAILS_LEN: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportReason {
+    Phishing,
+    Malware,
+    Spam,
+    Other,
+}
+
+impl ReportReason {
+    pub const ALL: [ReportReason; 4] = [
+        ReportReason::Phishing,
+        ReportReason::Malware,
+        ReportReason::Spam,
+        ReportReason::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportReason::Phishing => "phishing",
+            ReportReason::Malware => "malware",
+            ReportReason::Spam => "spam",
+            ReportReason::Other => "other",
+        }
+    }
+}
+
+impl TryFrom<&str> for ReportReason {
+    type Error = LinkError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.trim().to_ascii_lowercase();
+
+        ReportReason::ALL
+            .into_iter()
+            .find(|reason| reason.as_str() == value)
+            .ok_or_else(|| LinkError::InvalidReport(format!("unknown reason '{value}'")))
+    }
+}
+
+// Reports start `Open` and an operator settles them exactly once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportState {
+    #[default]
+    Open,
+    Dismissed,
+    Confirmed,
+}
+
+impl ReportState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportState::Open => "open",
+            ReportState::Dismissed => "dismissed",
+            ReportState::Confirmed => "confirmed",
+        }
+    }
+}
+
+impl TryFrom<&str> for ReportState {
+    type Error = LinkError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim() {
+            "open" => Ok(ReportState::Open),
+            "dismissed" => Ok(ReportState::Dismissed),
+            "confirmed" => Ok(ReportState::Confirmed),
+            _ => Err(LinkError::InvalidFormat),
+        }
+    }
+}
+
+// The reporter is only ever known by a salted hash of their address: enough
+// to spot the same person reporting twice, not enough to identify them.
+// An IPv6 host usually owns a whole /64, so only that prefix is hashed.
+pub fn reporter_hash(salt: &str, ip: IpAddr) -> String {
+    let ip = match ip.to_canonical() {
+        IpAddr::V6(v6) => IpAddr::V6(Ipv6Addr::from(u128::from(v6) & !(u128::MAX >> 64))),
+        v4 => v4,
+    };
+
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(ip.to_string().as_bytes());
+
+    hex::encode(hasher.finalize())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    id: Uuid,
+    link_id: LinkId,
+    reason: ReportReason,
+    details: Option<String>,
+    reporter_hash: String,
+    created_at: DateTime<Utc>,
+    state: ReportState,
+}
+
+impl Report {
+    pub fn new(
+        link_id: LinkId,
+        reason: ReportReason,
+        details: Option<String>,
+        reporter_hash: String,
+        created_at: DateTime<Utc>,
+    ) -> Result<Self, LinkError> {
+        let details = details
+            .map(|details| details.trim().to_string())
+            .filter(|details| !details.is_empty());
+
+        if details
+            .as_deref()
+            .is_some_and(|details| details.chars().count() > MAX_REPORT_DETAILS_LEN)
+        {
+            return Err(LinkError::InvalidReport(format!(
+                "details must be at most {MAX_REPORT_DETAILS_LEN} characters"
+            )));
+        }
+
+        Ok(Self {
+            id: Uuid::new_v4(),
+            link_id,
+            reason,
+            details,
+            reporter_hash,
+            created_at,
+            state: ReportState::Open,
+        })
+    }
+
+    // Rebuilds a stored report without re-validating it.
+    pub fn restore(
+        id: Uuid,
+        link_id: LinkId,
+        reason: ReportReason,
+        details: Option<String>,
+        reporter_hash: String,
+        created_at: DateTime<Utc>,
+        state: ReportState,
+    ) -> Self {
+        Self {
+            id,
+            link_id,
+            reason,
+            details,
+            reporter_hash,
+            created_at,
+            state,
+        }
+    }
+
+    pub fn with_state(mut self, state: ReportState) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn link_id(&self) -> &LinkId {
+        &self.link_id
+    }
+
+    pub fn reason(&self) -> ReportReason {
+        self.reason
+    }
+
+    pub fn details(&self) -> Option<&str> {
+        self.details.as_deref()
+    }
+
+    pub fn reporter_hash(&self) -> &str {
+        &self.reporter_hash
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    pub fn state(&self) -> ReportState {
+        self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reporters_are_hashed_with_the_salt() {
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+
+        assert_eq!(reporter_hash("pepper", ip), reporter_hash("pepper", ip));
+        assert_ne!(reporter_hash("pepper", ip), reporter_hash("salt", ip));
+        assert_ne!(
+            reporter_hash("pepper", ip),
+            reporter_hash("pepper", "203.0.113.8".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn ipv6_reporters_are_hashed_by_their_64_prefix() {
+        let hash = |ip: &str| reporter_hash("pepper", ip.parse().unwrap());
+
+        assert_eq!(hash("2001:db8:1:2::1"), hash("2001:db8:1:2:ffff::9"));
+        assert_ne!(hash("2001:db8:1:2::1"), hash("2001:db8:1:3::1"));
+        assert_eq!(hash("::ffff:203.0.113.7"), hash("203.0.113.7"));
+    }
+
+    #[test]
+    fn report_reasons_parse_case_insensitively() {
+        assert!(matches!(
+            ReportReason::try_from(" Phishing "),
+            Ok(ReportReason::Phishing)
+        ));
+        assert!(ReportReason::try_from("boring").is_err());
+    }
+}
@@ -0,0 +1,413 @@
+use crate::domain::errors::LinkError;
+use crate::domain::link::UserUrl;
+
+pub const MAX_RULES_PER_LINK: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Mobile,
+    Tablet,
+    Desktop,
+    Bot,
+}
+
+impl DeviceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceKind::Mobile => "mobile",
+            DeviceKind::Tablet => "tablet",
+            DeviceKind::Desktop => "desktop",
+            DeviceKind::Bot => "bot",
+        }
+    }
+}
+
+impl TryFrom<&str> for DeviceKind {
+    type Error = LinkError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "mobile" => Ok(DeviceKind::Mobile),
+            "tablet" => Ok(DeviceKind::Tablet),
+            "desktop" => Ok(DeviceKind::Desktop),
+            "bot" => Ok(DeviceKind::Bot),
+            other => Err(LinkError::InvalidRule(format!("unknown device '{other}'"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OsKind {
+    Ios,
+    Android,
+    Windows,
+    MacOs,
+    Linux,
+    Other,
+}
+
+impl OsKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OsKind::Ios => "ios",
+            OsKind::Android => "android",
+            OsKind::Windows => "windows",
+            OsKind::MacOs => "macos",
+            OsKind::Linux => "linux",
+            OsKind::Other => "other",
+        }
+    }
+}
+
+impl TryFrom<&str> for OsKind {
+    type Error = LinkError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "ios" => Ok(OsKind::Ios),
+            "android" => Ok(OsKind::Android),
+            "windows" => Ok(OsKind::Windows),
+            "macos" => Ok(OsKind::MacOs),
+            "linux" => Ok(OsKind::Linux),
+            "other" => Ok(OsKind::Other),
+            other => Err(LinkError::InvalidRule(format!("unknown os '{other}'"))),
+        }
+    }
+}
+
+// What is known about the visitor once the request has been classified.
+// Anything that could not be determined is `None` and never matches a
+// condition on that field.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Audience {
+    pub device: Option<DeviceKind>,
+    pub os: Option<OsKind>,
+    // Lowercased language tag the visitor prefers most, e.g. `pt-br`.
+    pub language: Option<String>,
+    // ISO 3166-1 alpha-2, uppercased.
+    pub country: Option<String>,
+}
+
+// Empty lists place no constraint; a rule with no conditions at all
+// matches everyone.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleConditions {
+    pub devices: Vec<DeviceKind>,
+    pub os: Vec<OsKind>,
+    pub languages: Vec<String>,
+    pub countries: Vec<String>,
+}
+
+impl RuleConditions {
+    pub fn normalised(self) -> Result<Self, LinkError> {
+        let languages = self
+            .languages
+            .into_iter()
+            .map(|language| language.trim().to_ascii_lowercase())
+            .map(|language| {
+                let valid = !language.is_empty()
+                    && language.len() <= 35
+                    && language
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-');
+
+                if valid {
+                    Ok(language)
+                } else {
+                    Err(LinkError::InvalidRule(format!(
+                        "invalid language '{language}'"
+                    )))
+                }
+            })
+            .collect::<Result<Vec<String>, LinkError>>()?;
+
+        let countries = self
+            .countries
+            .into_iter()
+            .map(|country| country.trim().to_ascii_uppercase())
+            .map(|country| {
+                if country.len() == 2 && country.chars().all(|c| c.is_ascii_uppercase()) {
+                    Ok(country)
+                } else {
+                    Err(LinkError::InvalidRule(format!(
+                        "invalid country '{country}'"
+                    )))
+                }
+            })
+            .collect::<Result<Vec<String>, LinkError>>()?;
+
+        Ok(Self {
+            devices: self.devices,
+            os: self.os,
+            languages,
+            countries,
+        })
+    }
+
+    pub fn matches(&self, audience: &Audience) -> bool {
+        fn allows<T: PartialEq>(wanted: &[T], actual: Option<&T>) -> bool {
+            wanted.is_empty() || actual.is_some_and(|actual| wanted.contains(actual))
+        }
+
+        // `en` covers `en-gb`; `en-gb` covers only itself.
+        let language_matches = self.languages.is_empty()
+            || audience.language.as_deref().is_some_and(|visitor| {
+                self.languages.iter().any(|wanted| {
+                    visitor == wanted
+                        || visitor
+                            .strip_prefix(wanted.as_str())
+                            .is_some_and(|rest| rest.starts_with('-'))
+                })
+            });
+
+        allows(&self.devices, audience.device.as_ref())
+            && allows(&self.os, audience.os.as_ref())
+            && allows(&self.countries, audience.country.as_ref())
+            && language_matches
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoutingRule {
+    conditions: RuleConditions,
+    destination: UserUrl,
+}
+
+impl RoutingRule {
+    pub fn new(conditions: RuleConditions, destination: UserUrl) -> Self {
+        Self {
+            conditions,
+            destination,
+        }
+    }
+
+    pub fn conditions(&self) -> &RuleConditions {
+        &self.conditions
+    }
+
+    pub fn destination(&self) -> &UserUrl {
+        &self.destination
+    }
+}
+
+// Rules are tried in order and the first match wins.
+pub fn find_rule<'a>(rules: &'a [RoutingRule], audience: &Audience) -> Option<&'a RoutingRule> {
+    rules.iter().find(|rule| rule.conditions.matches(audience))
+}
+
+pub const MAX_VARIANTS_PER_LINK: usize = 10;
+const MAX_VARIANT_WEIGHT: u32 = 10_000;
+
+// One arm of an A/B split. The name travels in the sticky cookie and in
+// click analytics, so it is kept short and cookie-safe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    name: String,
+    destination: UserUrl,
+    weight: u32,
+}
+
+impl Variant {
+    pub fn new(name: &str, destination: UserUrl, weight: u32) -> Result<Self, LinkError> {
+        let name = name.trim();
+
+        let valid_name = !name.is_empty()
+            && name.len() <= 32
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        if !valid_name {
+            return Err(LinkError::InvalidVariant(format!(
+                "name '{name}' must be 1-32 letters, digits, '-' or '_'"
+            )));
+        }
+
+        if weight > MAX_VARIANT_WEIGHT {
+            return Err(LinkError::InvalidVariant(format!(
+                "weight of '{name}' must be at most {MAX_VARIANT_WEIGHT}"
+            )));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            destination,
+            weight,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn destination(&self) -> &UserUrl {
+        &self.destination
+    }
+
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+}
+
+// A split needs distinct names and something to send traffic to; weight 0
+// parks a variant without losing its history.
+pub fn check_variants(variants: &[Variant]) -> Result<(), LinkError> {
+    if variants.len() > MAX_VARIANTS_PER_LINK {
+        return Err(LinkError::InvalidVariant(format!(
+            "at most {MAX_VARIANTS_PER_LINK} variants per link"
+        )));
+    }
+
+    for (index, variant) in variants.iter().enumerate() {
+        if variants[..index]
+            .iter()
+            .any(|other| other.name == variant.name)
+        {
+            return Err(LinkError::InvalidVariant(format!(
+                "duplicate name '{}'",
+                variant.name
+            )));
+        }
+    }
+
+    if !variants.is_empty() && total_weight(variants) == 0 {
+        return Err(LinkError::InvalidVariant(
+            "at least one variant needs a positive weight".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn total_weight(variants: &[Variant]) -> u32 {
+    variants.iter().map(|variant| variant.weight).sum()
+}
+
+// A returning visitor keeps the variant they saw before as long as it is
+// still live; everyone else is placed by `roll`, drawn uniformly from
+// `0..total_weight`.
+pub fn choose_variant<'a>(
+    variants: &'a [Variant],
+    sticky: Option<&str>,
+    roll: u32,
+) -> Option<&'a Variant> {
+    if let Some(kept) = sticky.and_then(|name| {
+        variants
+            .iter()
+            .find(|variant| variant.name == name && variant.weight > 0)
+    }) {
+        return Some(kept);
+    }
+
+    let mut remaining = roll;
+
+    variants.iter().find(|variant| {
+        if remaining < variant.weight {
+            true
+        } else {
+            remaining -= variant.weight;
+            false
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(conditions: RuleConditions, url: &str) -> RoutingRule {
+        RoutingRule::new(conditions, UserUrl::new(url.to_string()))
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let rules = vec![
+            rule(
+                RuleConditions {
+                    os: vec![OsKind::Ios],
+                    ..RuleConditions::default()
+                },
+                "https://apps.apple.com/app",
+            ),
+            rule(
+                RuleConditions {
+                    languages: vec!["fr".to_string()],
+                    countries: vec!["CA".to_string()],
+                    ..RuleConditions::default()
+                },
+                "https://example.com/fr-ca",
+            ),
+            rule(
+                RuleConditions {
+                    languages: vec!["fr".to_string()],
+                    ..RuleConditions::default()
+                },
+                "https://example.com/fr",
+            ),
+        ];
+        let fallback = UserUrl::new("https://example.com/".to_string());
+        let route_for = |audience: Audience| {
+            find_rule(&rules, &audience)
+                .map_or(&fallback, RoutingRule::destination)
+                .as_str()
+        };
+
+        assert_eq!(
+            route_for(Audience {
+                os: Some(OsKind::Ios),
+                language: Some("fr-fr".to_string()),
+                ..Audience::default()
+            }),
+            "https://apps.apple.com/app"
+        );
+        assert_eq!(
+            route_for(Audience {
+                language: Some("fr-ca".to_string()),
+                country: Some("CA".to_string()),
+                ..Audience::default()
+            }),
+            "https://example.com/fr-ca"
+        );
+        assert_eq!(
+            route_for(Audience {
+                language: Some("fr".to_string()),
+                ..Audience::default()
+            }),
+            "https://example.com/fr"
+        );
+        assert_eq!(
+            route_for(Audience {
+                language: Some("fry".to_string()),
+                ..Audience::default()
+            }),
+            "https://example.com/"
+        );
+        assert_eq!(route_for(Audience::default()), "https://example.com/");
+    }
+
+    #[test]
+    fn variants_are_picked_by_weight_and_stay_sticky() {
+        let variant = |name, weight| {
+            Variant::new(
+                name,
+                UserUrl::new(format!("https://example.com/{name}")),
+                weight,
+            )
+            .unwrap()
+        };
+        let variants = vec![variant("a", 1), variant("paused", 0), variant("b", 3)];
+        let pick = |sticky, roll| choose_variant(&variants, sticky, roll).map(Variant::name);
+
+        assert_eq!(total_weight(&variants), 4);
+        assert_eq!(pick(None, 0), Some("a"));
+        assert_eq!(pick(None, 1), Some("b"));
+        assert_eq!(pick(None, 3), Some("b"));
+        assert_eq!(pick(Some("a"), 3), Some("a"));
+        assert_eq!(pick(Some("paused"), 0), Some("a"));
+        assert_eq!(pick(Some("gone"), 2), Some("b"));
+
+        assert!(check_variants(&[variant("a", 1), variant("a", 2)]).is_err());
+        assert!(check_variants(&[variant("a", 0)]).is_err());
+        assert!(Variant::new("no spaces", UserUrl::new(String::new()), 1).is_err());
+    }
+}
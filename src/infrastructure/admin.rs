@@ -0,0 +1,328 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::SecondsFormat;
+use futures::stream;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::LinkError,
+    link::{LinkId, LinkStatus},
+    ports::{LinkPersistence, LinkQuery},
+    report::{Report, ReportState},
+};
+use crate::infrastructure::{
+    csrf::ScriptedRequest,
+    errors::ApiError,
+    handlers::AppState,
+    lists::reload_lists,
+    transfer::{decode_entries, encode_entries, ImportReport, TransferFormat},
+};
+
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+    #[serde(default)]
+    pub format: TransferFormat,
+    #[serde(default)]
+    pub clicks: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ImportParams {
+    #[serde(default)]
+    pub format: TransferFormat,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+// Pages are fetched lazily as the client reads, so exporting a large table
+// never holds more than one page in memory.
+pub async fn export_links<P, Q>(
+    State(state): State<AppState<P, Q>>,
+    Query(params): Query<ExportParams>,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let pages = stream::unfold(Some(0i64), move |offset| {
+        let service = state.link_service.clone();
+
+        async move {
+            let offset = offset?;
+
+            let chunk = service
+                .export_page(offset, EXPORT_PAGE_SIZE, params.clicks)
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|entries| {
+                    let next = (entries.len() as i64 == EXPORT_PAGE_SIZE)
+                        .then_some(offset + EXPORT_PAGE_SIZE);
+
+                    encode_entries(&entries, params.format, offset == 0).map(|bytes| (bytes, next))
+                });
+
+            match chunk {
+                Ok((bytes, next)) => Some((Ok(Bytes::from(bytes)), next)),
+                Err(e) => {
+                    tracing::error!("export failed at offset {offset}: {e}");
+                    Some((Err(std::io::Error::other(e)), None))
+                }
+            }
+        }
+    });
+
+    (
+        [(header::CONTENT_TYPE, params.format.content_type())],
+        Body::from_stream(pages),
+    )
+}
+
+pub async fn import_links<P, Q>(
+    _: ScriptedRequest,
+    State(state): State<AppState<P, Q>>,
+    Query(params): Query<ImportParams>,
+    body: Bytes,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let decoded = decode_entries(&body, params.format);
+
+    let mut entries = Vec::with_capacity(decoded.len());
+    let slots = decoded
+        .into_iter()
+        .map(|row| {
+            row.map(|entry| {
+                entries.push(entry);
+            })
+        })
+        .collect();
+
+    match state.link_service.import(entries, params.dry_run).await {
+        Ok(outcomes) => {
+            let report = ImportReport::new(params.dry_run, slots, outcomes);
+            let status = if report.failed + report.conflicts == 0 {
+                StatusCode::OK
+            } else {
+                StatusCode::MULTI_STATUS
+            };
+
+            (status, Json(report)).into_response()
+        }
+        Err(e) => ApiError(e).into_response(),
+    }
+}
+
+#[derive(Serialize)]
+pub struct CampaignReport {
+    pub campaign: String,
+    pub links: i64,
+    pub clicks: i64,
+    pub last_clicked_at: Option<String>,
+}
+
+pub async fn campaign_report<P, Q>(State(state): State<AppState<P, Q>>) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    match state.link_service.campaign_stats().await {
+        Ok(stats) => {
+            let report: Vec<CampaignReport> = stats
+                .into_iter()
+                .map(|stats| CampaignReport {
+                    campaign: stats.campaign,
+                    links: stats.links,
+                    clicks: stats.clicks,
+                    last_clicked_at: stats
+                        .last_clicked_at
+                        .map(|at| at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+                })
+                .collect();
+
+            Json(report).into_response()
+        }
+        Err(e) => ApiError(e).into_response(),
+    }
+}
+
+pub async fn reload_link_lists<P, Q>(
+    _: ScriptedRequest,
+    State(state): State<AppState<P, Q>>,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    match reload_lists(&state.link_service, &state.config) {
+        Ok(()) => (StatusCode::OK, "Lists reloaded").into_response(),
+        Err(e) => ApiError(LinkError::InvalidList(e)).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct StatusChange {
+    pub status: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct StatusReport {
+    pub id: String,
+    pub short_code: String,
+    pub status: &'static str,
+    pub reason: Option<String>,
+}
+
+pub async fn change_link_status<P, Q>(
+    State(state): State<AppState<P, Q>>,
+    Path(id): Path<String>,
+    Json(body): Json<StatusChange>,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let outcome = match (
+        LinkId::from_string(id),
+        LinkStatus::try_from(body.status.as_str()),
+    ) {
+        (Ok(id), Ok(status)) => {
+            state
+                .link_service
+                .change_status(id, status, body.reason)
+                .await
+        }
+        (Err(e), _) | (_, Err(e)) => Err(e),
+    };
+
+    match outcome {
+        Ok(link) => Json(StatusReport {
+            id: link.id().clone().into_inner().to_string(),
+            short_code: link.short_url().as_str().to_string(),
+            status: link.status().as_str(),
+            reason: link.status_reason().map(str::to_string),
+        })
+        .into_response(),
+        Err(e) => ApiError(e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ReportQueueParams {
+    // `open` unless asked otherwise; `all` lists every report.
+    #[serde(default = "default_report_state")]
+    pub state: String,
+    #[serde(default)]
+    pub offset: i64,
+    #[serde(default = "default_report_limit")]
+    pub limit: i64,
+}
+
+fn default_report_state() -> String {
+    ReportState::Open.as_str().to_string()
+}
+
+fn default_report_limit() -> i64 {
+    50
+}
+
+#[derive(Serialize)]
+pub struct ReportView {
+    pub id: String,
+    pub link_id: String,
+    pub reason: &'static str,
+    pub details: Option<String>,
+    pub state: &'static str,
+    pub created_at: String,
+}
+
+impl From<&Report> for ReportView {
+    fn from(report: &Report) -> Self {
+        Self {
+            id: report.id().to_string(),
+            link_id: report.link_id().clone().into_inner().to_string(),
+            reason: report.reason().as_str(),
+            details: report.details().map(str::to_string),
+            state: report.state().as_str(),
+            created_at: report
+                .created_at()
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+        }
+    }
+}
+
+pub async fn report_queue<P, Q>(
+    State(state): State<AppState<P, Q>>,
+    Query(params): Query<ReportQueueParams>,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let filter = match params.state.as_str() {
+        "all" => None,
+        other => match ReportState::try_from(other) {
+            Ok(filter) => Some(filter),
+            Err(_) => {
+                return ApiError(LinkError::InvalidInput(
+                    "state must be open, dismissed, confirmed or all".to_string(),
+                ))
+                .into_response()
+            }
+        },
+    };
+
+    match state
+        .link_service
+        .list_reports(filter, params.offset.max(0), params.limit.clamp(1, 500))
+        .await
+    {
+        Ok(reports) => {
+            Json(reports.iter().map(ReportView::from).collect::<Vec<_>>()).into_response()
+        }
+        Err(e) => ApiError(e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ReportDecision {
+    pub id: String,
+    pub decision: String,
+}
+
+// `/admin/reports/:id/dismiss` or `/admin/reports/:id/confirm`.
+pub async fn review_report<P, Q>(
+    _: ScriptedRequest,
+    State(state): State<AppState<P, Q>>,
+    Path(ReportDecision { id, decision }): Path<ReportDecision>,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let decision = match decision.as_str() {
+        "dismiss" => ReportState::Dismissed,
+        "confirm" => ReportState::Confirmed,
+        _ => return ApiError(LinkError::NotFound).into_response(),
+    };
+
+    let outcome = match Uuid::parse_str(&id) {
+        Ok(id) => state.link_service.review_report(id, decision).await,
+        Err(_) => Err(LinkError::InvalidFormat),
+    };
+
+    match outcome {
+        Ok(report) => Json(ReportView::from(&report)).into_response(),
+        Err(e) => ApiError(e).into_response(),
+    }
+}
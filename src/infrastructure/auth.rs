@@ -0,0 +1,63 @@
+use async_trait::async_trait;
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts},
+};
+
+use crate::domain::{
+    errors::LinkError,
+    link::key_matches,
+    ports::{LinkPersistence, LinkQuery},
+};
+use crate::infrastructure::{errors::ApiError, handlers::AppState};
+
+const API_KEY_HEADER: &str = "x-api-key";
+
+// Who is calling: `owner` is set when the request carried a valid API key.
+// Requests without a key are anonymous; a key that matches nobody is
+// rejected rather than silently downgraded.
+#[derive(Debug, Clone, Default)]
+pub struct ApiCaller {
+    pub owner: Option<String>,
+}
+
+fn presented_key(parts: &Parts) -> Option<&str> {
+    if let Some(value) = parts.headers.get(API_KEY_HEADER) {
+        return value.to_str().ok().map(str::trim);
+    }
+
+    parts
+        .headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+#[async_trait]
+impl<P, Q> FromRequestParts<AppState<P, Q>> for ApiCaller
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<P, Q>,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(presented) = presented_key(parts) else {
+            return Ok(Self::default());
+        };
+
+        state
+            .config
+            .api_keys
+            .iter()
+            .find(|(_, key)| key_matches(presented, key))
+            .map(|(owner, _)| Self {
+                owner: Some(owner.clone()),
+            })
+            .ok_or_else(|| ApiError(LinkError::Unauthorized("Invalid API key".to_string())))
+    }
+}
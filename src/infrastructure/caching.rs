@@ -0,0 +1,104 @@
+use axum::{
+    http::{header, Extensions, HeaderMap, HeaderValue, StatusCode, Version},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use tower_http::compression::{predicate::Predicate, CompressionLayer, DefaultPredicate};
+
+// Weak, because compression changes the bytes on the wire but not what
+// they mean.
+fn etag_for(body: &[u8]) -> String {
+    format!("W/\"{}\"", hex::encode(&Sha256::digest(body)[..16]))
+}
+
+fn http_date(at: DateTime<Utc>) -> String {
+    at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|candidate| candidate.trim() == "*" || opaque(candidate) == opaque(etag))
+}
+
+// HTTP dates only carry whole seconds.
+fn unmodified_since(headers: &HeaderMap, last_modified: DateTime<Utc>) -> bool {
+    headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+        .is_some_and(|since| last_modified.timestamp() <= since.timestamp())
+}
+
+// Answers a read with its body, or with 304 when the client's copy is still
+// current. `If-None-Match` takes precedence over `If-Modified-Since`.
+pub fn conditional_response(
+    request: &HeaderMap,
+    content_type: &'static str,
+    cache_control: &'static str,
+    last_modified: Option<DateTime<Utc>>,
+    body: Vec<u8>,
+) -> Response {
+    let etag = etag_for(&body);
+
+    let fresh = if request.contains_key(header::IF_NONE_MATCH) {
+        etag_matches(request, &etag)
+    } else {
+        last_modified.is_some_and(|at| unmodified_since(request, at))
+    };
+
+    let mut response = if fresh {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        (
+            [(header::CONTENT_TYPE, HeaderValue::from_static(content_type))],
+            body,
+        )
+            .into_response()
+    };
+
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(cache_control),
+    );
+
+    if let Ok(value) = HeaderValue::try_from(etag) {
+        headers.insert(header::ETAG, value);
+    }
+
+    if let Some(value) = last_modified.and_then(|at| HeaderValue::try_from(http_date(at)).ok()) {
+        headers.insert(header::LAST_MODIFIED, value);
+    }
+
+    response
+}
+
+// Redirects have no body worth compressing, and binary formats are already
+// compressed.
+fn compressible(status: StatusCode, _: Version, headers: &HeaderMap, _: &Extensions) -> bool {
+    let textual = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value.starts_with("text/")
+                || value.starts_with("application/json")
+                || value.starts_with("application/javascript")
+                || value.starts_with("image/svg+xml")
+        });
+
+    !status.is_redirection() && textual
+}
+
+pub fn compression_layer() -> CompressionLayer<impl Predicate> {
+    CompressionLayer::new()
+        .br(true)
+        .gzip(true)
+        .compress_when(DefaultPredicate::new().and(compressible))
+}
@@ -0,0 +1,210 @@
+use std::path::PathBuf;
+
+use crate::domain::link::{QueryConflict, RedirectKind};
+use std::time::Duration;
+
+// OWASP A05 Security Misconfiguration: every tunable is read from the
+// environment so nothing sensitive has to live in the binary.
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    pub database_url: String,
+    pub bulk_max_rows: usize,
+    // Absolute origin short links are published under, e.g. https://rl.example.
+    pub public_base_url: String,
+    // Show the preview page for every link instead of redirecting.
+    pub always_preview: bool,
+    pub dns_timeout: Duration,
+    // Used for links that do not pick their own redirect status.
+    pub default_redirect: RedirectKind,
+    // How long browsers may cache a 301/308 redirect.
+    pub permanent_redirect_max_age: Duration,
+    // `destination`, `incoming` or `append` for passthrough links.
+    pub query_conflict: QueryConflict,
+    // `allow`/`deny` rules for destinations, reloaded on SIGHUP.
+    pub domain_policy_file: Option<PathBuf>,
+    // Hash-prefix threat feed synced from upstream, also reloaded on SIGHUP.
+    pub threat_list_file: Option<PathBuf>,
+    // Canonical destinations drop `utm_*` and similar tracking parameters.
+    pub strip_tracking_params: bool,
+    // Default for the per-request `reuse` flag on owned links.
+    pub reuse_existing_links: bool,
+    // `(owner, key)` pairs from `API_KEYS=owner:key,owner:key`.
+    pub api_keys: Vec<(String, String)>,
+    // GeoLite2/GeoIP2 Country database for country routing rules.
+    pub geoip_db_path: Option<PathBuf>,
+    // Take the client address from the last `X-Forwarded-For` hop.
+    pub trust_forwarded_for: bool,
+    // Where scheduled links send visitors before launch; without it a
+    // built-in "coming soon" page is shown.
+    pub coming_soon_url: Option<String>,
+    // Salt for reporter address hashes. Required while reports can disable
+    // links: a value that changed on restart would let the same visitor
+    // report a link again.
+    pub report_salt: String,
+    // Open abuse reports that disable an active link; 0 turns that off.
+    pub report_threshold: i64,
+    // `Strict-Transport-Security` max-age in seconds; 0 leaves it out.
+    // Defaults to a year when the public base URL is HTTPS.
+    pub hsts_max_age: u64,
+    pub hsts_include_subdomains: bool,
+    // Origins allowed to call the JSON API from a browser, from
+    // `CORS_ALLOWED_ORIGINS=https://a.example,https://b.example`. Empty keeps
+    // the API same-origin only.
+    pub cors_allowed_origins: Vec<String>,
+    // How long browsers may cache a preflight answer.
+    pub cors_max_age: Duration,
+    // Largest request body accepted outside the admin routes.
+    pub max_body_bytes: usize,
+    // Admin imports carry whole link tables, so they get their own limit.
+    pub max_import_bytes: usize,
+    // Time to read the request and produce a response, streamed bodies
+    // aside.
+    pub request_timeout: Duration,
+    // The same for admin routes, whose imports carry whole link tables.
+    pub admin_request_timeout: Duration,
+    // Per statement, and for waiting on a pooled connection.
+    pub db_timeout: Duration,
+    // Served under `/static`; the home page script lives here.
+    pub static_dir: PathBuf,
+    pub admin_user: String,
+    // Admin routes are only mounted when a password is configured.
+    pub admin_password: Option<String>,
+}
+
+impl AppConfig {
+    pub fn from_env() -> Self {
+        let public_base_url = env_or("PUBLIC_BASE_URL", "http://localhost:8080".to_string());
+        let report_threshold = env_or("REPORT_THRESHOLD", 5);
+        let report_salt = std::env::var("REPORT_SALT")
+            .ok()
+            .filter(|value| !value.is_empty());
+
+        if report_threshold > 0 && report_salt.is_none() {
+            panic!("FATAL: REPORT_SALT NOT SET WHILE REPORT_THRESHOLD IS ABOVE 0");
+        }
+
+        let default_hsts = if public_base_url.starts_with("https://") {
+            31_536_000
+        } else {
+            0
+        };
+
+        Self {
+            database_url: std::env::var("DATABASE_URL").expect("FATAL: DATABASE_URL NOT SET"),
+            bulk_max_rows: env_or("BULK_MAX_ROWS", 500),
+            public_base_url,
+            always_preview: env_or("ALWAYS_PREVIEW", false),
+            dns_timeout: Duration::from_millis(env_or("DNS_TIMEOUT_MS", 2000)),
+            default_redirect: std::env::var("DEFAULT_REDIRECT_STATUS")
+                .map_or(Some(RedirectKind::default()), |value| {
+                    value
+                        .trim()
+                        .parse()
+                        .ok()
+                        .and_then(|status: u16| RedirectKind::try_from(status).ok())
+                })
+                .expect("FATAL: DEFAULT_REDIRECT_STATUS MUST BE 301, 302, 307 OR 308"),
+            permanent_redirect_max_age: Duration::from_secs(env_or(
+                "PERMANENT_REDIRECT_MAX_AGE",
+                86400,
+            )),
+            query_conflict: env_or("PASSTHROUGH_QUERY_CONFLICT", QueryConflict::default()),
+            domain_policy_file: std::env::var("DOMAIN_POLICY_FILE").ok().map(PathBuf::from),
+            threat_list_file: std::env::var("THREAT_LIST_FILE").ok().map(PathBuf::from),
+            strip_tracking_params: env_or("STRIP_TRACKING_PARAMS", false),
+            reuse_existing_links: env_or("REUSE_EXISTING_LINKS", false),
+            api_keys: parse_api_keys(&std::env::var("API_KEYS").unwrap_or_default()),
+            geoip_db_path: std::env::var("GEOIP_DB_PATH").ok().map(PathBuf::from),
+            trust_forwarded_for: env_or("TRUST_FORWARDED_FOR", false),
+            coming_soon_url: std::env::var("COMING_SOON_URL")
+                .ok()
+                .filter(|value| !value.is_empty()),
+            report_salt: report_salt.unwrap_or_default(),
+            report_threshold,
+            hsts_max_age: env_or("HSTS_MAX_AGE", default_hsts),
+            hsts_include_subdomains: env_or("HSTS_INCLUDE_SUBDOMAINS", false),
+            cors_allowed_origins: parse_list(
+                &std::env::var("CORS_ALLOWED_ORIGINS").unwrap_or_default(),
+            ),
+            cors_max_age: Duration::from_secs(env_or("CORS_MAX_AGE", 600)),
+            max_body_bytes: env_or("MAX_BODY_BYTES", 1024 * 1024),
+            max_import_bytes: env_or("MAX_IMPORT_BYTES", 32 * 1024 * 1024),
+            request_timeout: Duration::from_millis(env_or("REQUEST_TIMEOUT_MS", 10_000)),
+            admin_request_timeout: Duration::from_millis(env_or(
+                "ADMIN_REQUEST_TIMEOUT_MS",
+                300_000,
+            )),
+            db_timeout: Duration::from_millis(env_or("DB_TIMEOUT_MS", 5_000)),
+            static_dir: std::env::var("STATIC_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| PathBuf::from("static")),
+            admin_user: env_or("ADMIN_USER", "admin".to_string()),
+            admin_password: std::env::var("ADMIN_PASSWORD")
+                .ok()
+                .filter(|value| !value.is_empty()),
+        }
+    }
+
+    pub fn short_link(&self, code: &str) -> String {
+        format!("{}/l/{}", self.public_base_url.trim_end_matches('/'), code)
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            database_url: String::new(),
+            bulk_max_rows: 500,
+            public_base_url: "http://localhost:8080".to_string(),
+            always_preview: false,
+            dns_timeout: Duration::from_millis(2000),
+            default_redirect: RedirectKind::default(),
+            permanent_redirect_max_age: Duration::from_secs(86400),
+            query_conflict: QueryConflict::default(),
+            domain_policy_file: None,
+            threat_list_file: None,
+            strip_tracking_params: false,
+            reuse_existing_links: false,
+            api_keys: Vec::new(),
+            geoip_db_path: None,
+            trust_forwarded_for: false,
+            coming_soon_url: None,
+            report_salt: String::new(),
+            report_threshold: 5,
+            hsts_max_age: 0,
+            hsts_include_subdomains: false,
+            cors_allowed_origins: Vec::new(),
+            cors_max_age: Duration::from_secs(600),
+            max_body_bytes: 1024 * 1024,
+            max_import_bytes: 32 * 1024 * 1024,
+            request_timeout: Duration::from_millis(10_000),
+            admin_request_timeout: Duration::from_millis(300_000),
+            db_timeout: Duration::from_millis(5_000),
+            static_dir: PathBuf::from("static"),
+            admin_user: "admin".to_string(),
+            admin_password: None,
+        }
+    }
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+fn parse_list(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|item| item.trim().trim_end_matches('/').to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn parse_api_keys(raw: &str) -> Vec<(String, String)> {
+    raw.split(',')
+        .filter_map(|pair| pair.split_once(':'))
+        .map(|(owner, key)| (owner.trim().to_string(), key.trim().to_string()))
+        .filter(|(owner, key)| !owner.is_empty() && !key.is_empty())
+        .collect()
+}
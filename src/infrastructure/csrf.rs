@@ -0,0 +1,241 @@
+use async_trait::async_trait;
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, Request},
+    http::{header, request::Parts, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use rand::RngCore;
+use serde::de::DeserializeOwned;
+
+use crate::domain::{
+    errors::LinkError,
+    link::key_matches,
+    ports::{LinkPersistence, LinkQuery},
+};
+use crate::infrastructure::{
+    auth::ApiCaller,
+    config::AppConfig,
+    errors::{ApiError, PageError},
+    handlers::{cookie_value, AppState},
+};
+
+pub const CSRF_COOKIE: &str = "rl_csrf";
+const CSRF_FIELD: &str = "csrf_token";
+
+const TOKEN_BYTES: usize = 32;
+
+// OWASP A01 Broken Access Control: double-submit tokens. Every form carries
+// the token in a hidden `csrf_token` field and the browser sends it back as
+// a cookie; another site can make the browser send the cookie, but cannot
+// read it to fill in the field.
+#[derive(Debug, Clone)]
+pub struct CsrfToken {
+    value: String,
+    // Not seen in the request, so the response has to set the cookie.
+    minted: bool,
+}
+
+impl CsrfToken {
+    // The visitor's existing token is reused so several open tabs all stay
+    // valid.
+    pub fn for_request(headers: &HeaderMap) -> Self {
+        match cookie_value(headers, CSRF_COOKIE).filter(|value| is_well_formed(value)) {
+            Some(value) => Self {
+                value,
+                minted: false,
+            },
+            None => {
+                let mut bytes = [0u8; TOKEN_BYTES];
+                rand::thread_rng().fill_bytes(&mut bytes);
+
+                Self {
+                    value: hex::encode(bytes),
+                    minted: true,
+                }
+            }
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn attach(&self, config: &AppConfig, mut response: Response) -> Response {
+        if !self.minted {
+            return response;
+        }
+
+        let secure = if config.public_base_url.starts_with("https://") {
+            "; Secure"
+        } else {
+            ""
+        };
+        let cookie = format!(
+            "{CSRF_COOKIE}={}; Path=/; HttpOnly; SameSite=Strict{secure}",
+            self.value
+        );
+
+        if let Ok(value) = HeaderValue::try_from(cookie) {
+            response.headers_mut().append(header::SET_COOKIE, value);
+        }
+
+        response
+    }
+}
+
+fn is_well_formed(value: &str) -> bool {
+    value.len() == TOKEN_BYTES * 2 && value.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+fn verify(headers: &HeaderMap, submitted: &str) -> bool {
+    cookie_value(headers, CSRF_COOKIE)
+        .filter(|expected| is_well_formed(expected))
+        .is_some_and(|expected| key_matches(submitted, &expected))
+}
+
+// Fetch metadata first: every current browser sends it, and it cannot be
+// forged by page script. Older browsers fall back to `Origin`. A request
+// with neither is not from a browser, so only the token is checked.
+fn same_origin(headers: &HeaderMap, config: &AppConfig) -> bool {
+    if let Some(site) = headers.get("sec-fetch-site") {
+        return matches!(site.to_str(), Ok("same-origin" | "none"));
+    }
+
+    let Some(origin) = headers.get(header::ORIGIN) else {
+        return true;
+    };
+
+    url::Url::parse(&config.public_base_url)
+        .is_ok_and(|base| origin.as_bytes() == base.origin().ascii_serialization().as_bytes())
+}
+
+fn rejected() -> Response {
+    PageError(LinkError::FormRejected).into_response()
+}
+
+// A urlencoded form from one of our own pages. Callers with a valid API key
+// are exempt: browsers never attach the key on their own, so a forged
+// request cannot carry one.
+pub struct CsrfForm<T>(pub T);
+
+#[async_trait]
+impl<P, Q, T> FromRequest<AppState<P, Q>> for CsrfForm<T>
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+    T: DeserializeOwned,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &AppState<P, Q>) -> Result<Self, Self::Rejection> {
+        let (mut parts, body) = req.into_parts();
+
+        let caller = ApiCaller::from_request_parts(&mut parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let exempt = caller.owner.is_some();
+
+        if !exempt && !same_origin(&parts.headers, &state.config) {
+            tracing::warn!("rejected a cross-origin form submission");
+            return Err(rejected());
+        }
+
+        let is_form = parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+
+        if !is_form {
+            return Err(PageError(LinkError::UnsupportedMediaType(
+                "Expected a form submission".to_string(),
+            ))
+            .into_response());
+        }
+
+        let headers = parts.headers.clone();
+        let bytes = Bytes::from_request(Request::from_parts(parts, body), state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        if !exempt {
+            let submitted = url::form_urlencoded::parse(&bytes)
+                .find(|(key, _)| key == CSRF_FIELD)
+                .map(|(_, value)| value.into_owned())
+                .unwrap_or_default();
+
+            if !verify(&headers, &submitted) {
+                return Err(rejected());
+            }
+        }
+
+        serde_urlencoded::from_bytes(&bytes)
+            .map(CsrfForm)
+            .map_err(|e| {
+                // serde's text names fields and echoes values; it stays in
+                // the log.
+                tracing::debug!("rejected a malformed form: {e}");
+                PageError(LinkError::InvalidInput(
+                    "The form is incomplete or has a malformed field".to_string(),
+                ))
+                .into_response()
+            })
+    }
+}
+
+// A scripted admin call: a body type a cross-site page cannot send without a
+// CORS preflight, which the admin routes never grant, or a valid API key.
+// Basic auth alone is not enough since browsers attach it on their own.
+pub struct ScriptedRequest;
+
+// What a plain HTML form or a no-cors fetch may send unasked. A missing
+// content type counts too: an empty no-cors POST has none.
+fn is_simple_content_type(headers: &HeaderMap) -> bool {
+    let Some(value) = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return true;
+    };
+
+    let essence = value
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    matches!(
+        essence.as_str(),
+        "" | "application/x-www-form-urlencoded" | "multipart/form-data" | "text/plain"
+    )
+}
+
+#[async_trait]
+impl<P, Q> FromRequestParts<AppState<P, Q>> for ScriptedRequest
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<P, Q>,
+    ) -> Result<Self, Self::Rejection> {
+        let caller = ApiCaller::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        if caller.owner.is_none() && is_simple_content_type(&parts.headers) {
+            tracing::warn!("rejected an admin call that a browser could have forged");
+            return Err(ApiError(LinkError::UnsupportedMediaType(
+                "Send a JSON or CSV body with its Content-Type, or an API key".to_string(),
+            ))
+            .into_response());
+        }
+
+        Ok(Self)
+    }
+}
@@ -0,0 +1,272 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::LinkError,
+    link::LinkId,
+    ports::{LinkPersistence, LinkQuery},
+    report::ReportState,
+};
+use crate::infrastructure::{
+    csrf::{CsrfForm, CsrfToken},
+    errors::{error_status, public_message, with_retry_after},
+    handlers::AppState,
+    pages::{
+        render, AdminMessagePage, DashboardPage, LinkDetailPage, LinkSummary, ReportQueuePage,
+        ReportSummary,
+    },
+};
+
+const DASHBOARD_PAGE_SIZE: i64 = 50;
+const CHART_DAYS: u32 = 30;
+
+#[derive(Deserialize)]
+pub struct DashboardParams {
+    #[serde(default)]
+    pub q: String,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+fn dashboard_href(query: &str, offset: i64) -> String {
+    let mut params = url::form_urlencoded::Serializer::new(String::new());
+
+    if !query.is_empty() {
+        params.append_pair("q", query);
+    }
+    if offset > 0 {
+        params.append_pair("offset", &offset.to_string());
+    }
+
+    match params.finish() {
+        params if params.is_empty() => "/admin".to_string(),
+        params => format!("/admin?{params}"),
+    }
+}
+
+// The message is only ever shown to the operator, but internal details
+// still stay in the log.
+fn failure(error: LinkError, action: &str, back: String) -> Response {
+    let message = public_message(&error);
+
+    let response = render(
+        error_status(&error),
+        AdminMessagePage {
+            title: format!("Could not complete the {action}"),
+            message,
+            back,
+        },
+    );
+
+    with_retry_after(&error, response)
+}
+
+pub async fn dashboard<P, Q>(
+    State(state): State<AppState<P, Q>>,
+    Query(params): Query<DashboardParams>,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let query = params.q.trim().to_string();
+    let offset = params.offset.max(0);
+
+    // One extra row tells whether there is a next page.
+    let links = match state
+        .link_service
+        .search(Some(&query), offset, DASHBOARD_PAGE_SIZE + 1)
+        .await
+    {
+        Ok(links) => links,
+        Err(e) => return failure(e, "search", "/admin".to_string()),
+    };
+
+    let next_page = (links.len() as i64 > DASHBOARD_PAGE_SIZE)
+        .then(|| dashboard_href(&query, offset + DASHBOARD_PAGE_SIZE));
+    let previous_page =
+        (offset > 0).then(|| dashboard_href(&query, (offset - DASHBOARD_PAGE_SIZE).max(0)));
+
+    render(
+        StatusCode::OK,
+        DashboardPage {
+            links: links
+                .iter()
+                .take(DASHBOARD_PAGE_SIZE as usize)
+                .map(LinkSummary::from)
+                .collect(),
+            query,
+            previous_page,
+            next_page,
+        },
+    )
+}
+
+pub async fn link_detail<P, Q>(
+    State(state): State<AppState<P, Q>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let back = "/admin".to_string();
+
+    let link = match LinkId::from_string(id) {
+        Ok(id) => state.link_service.find(id).await,
+        Err(e) => Err(e),
+    };
+    let link = match link {
+        Ok(link) => link,
+        Err(e) => return failure(e, "lookup", back),
+    };
+
+    let details = futures::try_join!(
+        state.link_service.stats(link.id().clone()),
+        state
+            .link_service
+            .daily_clicks(link.id().clone(), CHART_DAYS),
+    );
+    let (stats, days) = match details {
+        Ok(details) => details,
+        Err(e) => return failure(e, "lookup", back),
+    };
+
+    let token = CsrfToken::for_request(&headers);
+    let page = LinkDetailPage::new(
+        token.value().to_string(),
+        state.config.short_link(link.short_url().as_str()),
+        &link,
+        stats,
+        &days,
+    );
+
+    token.attach(&state.config, render(StatusCode::OK, page))
+}
+
+#[derive(Deserialize)]
+pub struct LinkAction {
+    // `disable`, `block`, `enable` or `delete`.
+    pub action: String,
+    #[serde(default)]
+    pub reason: String,
+}
+
+pub async fn manage_link<P, Q>(
+    State(state): State<AppState<P, Q>>,
+    Path(id): Path<String>,
+    CsrfForm(form): CsrfForm<LinkAction>,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let back = format!("/admin/links/{id}");
+
+    let id = match LinkId::from_string(id) {
+        Ok(id) => id,
+        Err(e) => return failure(e, "change", "/admin".to_string()),
+    };
+    let reason = Some(form.reason);
+    let service = &state.link_service;
+
+    // A deleted link has no page left to return to.
+    let outcome = match form.action.as_str() {
+        "disable" => service.disable(id, reason).await.map(|_| false),
+        "block" => service.block(id, reason).await.map(|_| false),
+        "enable" => service.enable(id).await.map(|_| false),
+        "delete" => service.delete(id).await.map(|_| true),
+        _ => Err(LinkError::InvalidFormat),
+    };
+
+    match outcome {
+        Ok(true) => Redirect::to("/admin").into_response(),
+        Ok(false) => Redirect::to(&back).into_response(),
+        Err(e) => failure(e, "change", back),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AbuseQueueParams {
+    #[serde(default)]
+    pub state: Option<String>,
+}
+
+const QUEUE_STATES: [&str; 4] = ["open", "dismissed", "confirmed", "all"];
+
+pub async fn abuse_queue<P, Q>(
+    State(state): State<AppState<P, Q>>,
+    Query(params): Query<AbuseQueueParams>,
+    headers: HeaderMap,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let shown = params
+        .state
+        .filter(|shown| QUEUE_STATES.contains(&shown.as_str()))
+        .unwrap_or_else(|| ReportState::Open.as_str().to_string());
+    let filter = ReportState::try_from(shown.as_str()).ok();
+
+    let reports = match state
+        .link_service
+        .list_reports(filter, 0, DASHBOARD_PAGE_SIZE)
+        .await
+    {
+        Ok(reports) => reports,
+        Err(e) => return failure(e, "listing", "/admin".to_string()),
+    };
+
+    let token = CsrfToken::for_request(&headers);
+    let page = ReportQueuePage {
+        csrf_token: token.value().to_string(),
+        filters: QUEUE_STATES
+            .into_iter()
+            .map(|option| (option, option == shown))
+            .collect(),
+        reports: reports.iter().map(ReportSummary::from).collect(),
+    };
+
+    token.attach(&state.config, render(StatusCode::OK, page))
+}
+
+#[derive(Deserialize)]
+pub struct ReviewForm {
+    // `dismiss` or `confirm`.
+    pub decision: String,
+}
+
+pub async fn review_report_form<P, Q>(
+    State(state): State<AppState<P, Q>>,
+    Path(id): Path<String>,
+    CsrfForm(form): CsrfForm<ReviewForm>,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let back = "/admin/abuse".to_string();
+
+    let decision = match form.decision.as_str() {
+        "dismiss" => ReportState::Dismissed,
+        "confirm" => ReportState::Confirmed,
+        _ => return failure(LinkError::InvalidFormat, "review", back),
+    };
+
+    let outcome = match Uuid::parse_str(&id) {
+        Ok(id) => state.link_service.review_report(id, decision).await,
+        Err(_) => Err(LinkError::InvalidFormat),
+    };
+
+    match outcome {
+        Ok(_) => Redirect::to(&back).into_response(),
+        Err(e) => failure(e, "review", back),
+    }
+}
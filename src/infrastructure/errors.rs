@@ -0,0 +1,125 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use crate::domain::errors::{ErrorKind, LinkError, UrlError};
+use crate::infrastructure::pages::{render, MessagePage};
+
+// How long a client should wait before retrying an overloaded service.
+const RETRY_AFTER_SECONDS: &str = "5";
+
+// The one place a `LinkError` becomes an HTTP status. HTML pages use it too,
+// so a page and its API counterpart always agree.
+pub fn error_status(error: &LinkError) -> StatusCode {
+    match error {
+        LinkError::InvalidUrl(UrlError::Unresolvable) | LinkError::InvalidList(_) => {
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+        LinkError::Blocked(_) => StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
+        _ => match error.kind() {
+            ErrorKind::InvalidInput => StatusCode::BAD_REQUEST,
+            ErrorKind::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorKind::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorKind::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorKind::TimedOut => StatusCode::REQUEST_TIMEOUT,
+            ErrorKind::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            ErrorKind::NotFound => StatusCode::NOT_FOUND,
+            ErrorKind::Conflict => StatusCode::CONFLICT,
+            ErrorKind::Forbidden => StatusCode::FORBIDDEN,
+            ErrorKind::Gone => StatusCode::GONE,
+            ErrorKind::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorKind::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        },
+    }
+}
+
+// OWASP A05 Security Misconfiguration: internal errors carry database
+// messages and the like, so they are logged here and replaced by a generic
+// sentence.
+pub fn public_message(error: &LinkError) -> String {
+    if error.kind() == ErrorKind::Internal {
+        tracing::error!("request failed: {error}");
+        "An internal error occurred.".to_string()
+    } else {
+        error.to_string()
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+    code: &'static str,
+}
+
+// `{"error": "<message>", "code": "<code>"}`; clients should branch on
+// `code`, which stays stable when messages are reworded.
+#[derive(Debug)]
+pub struct ApiError(pub LinkError);
+
+impl From<LinkError> for ApiError {
+    fn from(error: LinkError) -> Self {
+        Self(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = error_status(&self.0);
+        let body = ErrorBody {
+            error: public_message(&self.0),
+            code: self.0.code(),
+        };
+
+        with_retry_after(&self.0, (status, Json(body)).into_response())
+    }
+}
+
+// The page counterpart of `ApiError`, for routes a browser visits directly.
+// Messages are escaped by the template, so ones that echo the request are
+// safe to show.
+#[derive(Debug)]
+pub struct PageError(pub LinkError);
+
+impl From<LinkError> for PageError {
+    fn from(error: LinkError) -> Self {
+        Self(error)
+    }
+}
+
+impl IntoResponse for PageError {
+    fn into_response(self) -> Response {
+        let status = error_status(&self.0);
+        let page = MessagePage {
+            heading: format!(
+                "{} {}",
+                status.as_u16(),
+                status.canonical_reason().unwrap_or("Error")
+            ),
+            message: public_message(&self.0),
+            warning: self.0.kind() == ErrorKind::Forbidden,
+        };
+
+        with_retry_after(&self.0, render(status, page))
+    }
+}
+
+pub fn with_retry_after(error: &LinkError, mut response: Response) -> Response {
+    let retry_after = match error {
+        LinkError::TooManyAttempts { retry_after } => {
+            header::HeaderValue::from((*retry_after).max(1))
+        }
+        _ if error.kind() == ErrorKind::Unavailable => {
+            header::HeaderValue::from_static(RETRY_AFTER_SECONDS)
+        }
+        _ => return response,
+    };
+
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, retry_after);
+
+    response
+}
@@ -0,0 +1,39 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::path::Path;
+
+use maxminddb::{geoip2, Reader};
+
+use crate::domain::ports::GeoLocator;
+
+// Reads a GeoLite2/GeoIP2 Country (or City) database into memory once.
+pub struct MaxMindLocator {
+    reader: Reader<Vec<u8>>,
+}
+
+impl MaxMindLocator {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        Reader::open_readfile(path)
+            .map(|reader| Self { reader })
+            .map_err(|e| format!("{}: {e}", path.display()))
+    }
+}
+
+impl fmt::Debug for MaxMindLocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MaxMindLocator")
+            .field("database_type", &self.reader.metadata.database_type)
+            .finish()
+    }
+}
+
+impl GeoLocator for MaxMindLocator {
+    fn country(&self, ip: IpAddr) -> Option<String> {
+        let record: geoip2::Country = self.reader.lookup(ip).ok()?;
+
+        record
+            .country
+            .and_then(|country| country.iso_code)
+            .map(str::to_string)
+    }
+}
@@ -1,17 +1,41 @@
 use axum::{
-    extract::{Form, Path, State},
-    http::StatusCode,
-    response::{Html, IntoResponse, Redirect},
+    body::Bytes,
+    extract::{ConnectInfo, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Json,
 };
 
-use serde::Deserialize;
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use serde::{
+    de::{self, DeserializeSeed, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
 
-use crate::application::service::LinkService;
+use crate::application::{
+    audience::RequestContext,
+    command::{NewLink, NewRoutingRule, NewVariant, RedirectTarget, Visit, VisitMode},
+    service::LinkService,
+};
 use crate::domain::{
     errors::LinkError,
-    link::LinkId,
-    ports::{LinkPersistence, LinkQuery},
+    link::{Link, LinkId, LinkKey, RedirectKind, ShortUrl, Utm},
+    ports::{BatchMode, LinkPersistence, LinkQuery},
+};
+use crate::infrastructure::auth::ApiCaller;
+use crate::infrastructure::caching::conditional_response;
+use crate::infrastructure::config::AppConfig;
+use crate::infrastructure::csrf::{CsrfForm, CsrfToken};
+use crate::infrastructure::errors::{error_status, public_message, ApiError, PageError};
+use crate::infrastructure::pages::{
+    render, ComingSoonPage, CreatedResult, HomePage, MessagePage, PreviewPage, UnavailablePage,
+    WarningPage,
 };
+use crate::infrastructure::qr::QrCache;
+use crate::infrastructure::rules::{RuleRecord, VariantRecord};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -21,85 +45,550 @@ where
     Q: LinkQuery + Send + Sync + 'static,
 {
     pub link_service: Arc<LinkService<P, Q>>,
+    pub config: Arc<AppConfig>,
+    pub qr_cache: Arc<QrCache>,
 }
 
 #[derive(Clone, Deserialize)]
 pub struct CreateLinkForm {
     pub long_url: String,
+    pub alias: Option<String>,
+    // `datetime-local` input, read as UTC; RFC 3339 is accepted as well.
+    pub expires_at: Option<String>,
+    pub utm_source: Option<String>,
+    pub utm_medium: Option<String>,
+    pub utm_campaign: Option<String>,
+    pub utm_term: Option<String>,
+    pub utm_content: Option<String>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+pub struct UtmFields {
+    pub source: Option<String>,
+    pub medium: Option<String>,
+    pub campaign: Option<String>,
+    pub term: Option<String>,
+    pub content: Option<String>,
+}
+
+impl From<UtmFields> for Utm {
+    fn from(fields: UtmFields) -> Self {
+        Self {
+            source: fields.source,
+            medium: fields.medium,
+            campaign: fields.campaign,
+            term: fields.term,
+            content: fields.content,
+        }
+    }
+}
+
+pub async fn home<P, Q>(
+    State(state): State<AppState<P, Q>>,
+    headers: HeaderMap,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let token = CsrfToken::for_request(&headers);
+    let page = HomePage {
+        csrf_token: token.value().to_string(),
+        ..HomePage::default()
+    };
+
+    token.attach(&state.config, render(StatusCode::OK, page))
 }
 
+// The home page form posts here. Success and failure both render the home
+// page, with the outcome in its `#result` section.
 pub async fn create_link<P, Q>(
     State(state): State<AppState<P, Q>>,
-    Form(form): Form<CreateLinkForm>,
+    caller: ApiCaller,
+    headers: HeaderMap,
+    CsrfForm(form): CsrfForm<CreateLinkForm>,
 ) -> impl IntoResponse
 where
     P: LinkPersistence + Send + Sync + 'static,
     Q: LinkQuery + Send + Sync + 'static,
 {
-    match state.link_service.create(form.long_url).await {
-        Ok(link_id) => (
-            StatusCode::CREATED,
-            Html(format!(
-                "<div id='result'>Link created with ID: {:?} </div>",
-                link_id
-            )),
-        )
-            .into_response(),
+    let outcome = match parse_form_timestamp(form.expires_at.clone(), LinkError::InvalidExpiry) {
+        Ok(expires_at) => {
+            let request = NewLink {
+                alias: form.alias.clone(),
+                expires_at,
+                owner: caller.owner,
+                reuse_existing: state.config.reuse_existing_links,
+                utm: Utm {
+                    source: form.utm_source,
+                    medium: form.utm_medium,
+                    campaign: form.utm_campaign,
+                    term: form.utm_term,
+                    content: form.utm_content,
+                },
+                ..NewLink::from_url(form.long_url.clone())
+            };
 
-        Err(LinkError::InvalidUrl) => (
-            StatusCode::BAD_REQUEST,
-            Html("<h3>The provided URL is invalid.</h3>".to_string()),
-        )
-            .into_response(),
+            state.link_service.create_link(request).await
+        }
+        Err(e) => Err(e),
+    };
 
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Html("<h3>An internal error prevented link creation.</h3>".to_string()),
-        )
-            .into_response(),
-    }
+    // API-key callers skip the token check and may not have a cookie yet.
+    let token = CsrfToken::for_request(&headers);
+
+    let response = match outcome {
+        Ok(link) => {
+            let short_code = link.short_url().as_str().to_string();
+
+            render(
+                StatusCode::CREATED,
+                HomePage {
+                    csrf_token: token.value().to_string(),
+                    result: Some(CreatedResult {
+                        id: link.id().clone().into_inner().to_string(),
+                        short_link: state.config.short_link(&short_code),
+                        short_code,
+                        delete_key: link.delete_hash_code().value().to_string(),
+                    }),
+                    ..HomePage::default()
+                },
+            )
+        }
+
+        Err(e) => {
+            let status = error_status(&e);
+            let error = match e {
+                LinkError::InvalidUrl(e) => format!("The provided URL is invalid: {e}"),
+                LinkError::ThreatDetected(_) => "The destination is listed as unsafe.".to_string(),
+                e => public_message(&e),
+            };
+
+            render(
+                status,
+                HomePage {
+                    csrf_token: token.value().to_string(),
+                    long_url: form.long_url,
+                    alias: form.alias.unwrap_or_default(),
+                    expires_at: form.expires_at.unwrap_or_default(),
+                    result: None,
+                    error: Some(error),
+                },
+            )
+        }
+    };
+
+    token.attach(&state.config, response)
 }
 
-pub async fn redirect_link<P, Q>(
-    Path(code): Path<String>,
+#[derive(Deserialize)]
+pub struct CreateLinkRequest {
+    pub url: String,
+    pub alias: Option<String>,
+    pub active_from: Option<String>,
+    pub expires_at: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Falls back to `REUSE_EXISTING_LINKS` when omitted.
+    pub reuse: Option<bool>,
+    // 301, 302, 307 or 308; falls back to `DEFAULT_REDIRECT_STATUS`.
+    pub redirect_status: Option<u16>,
+    #[serde(default)]
+    pub passthrough: bool,
+    #[serde(default)]
+    pub utm: UtmFields,
+    #[serde(default)]
+    pub routing_rules: Vec<RuleRecord>,
+    #[serde(default)]
+    pub variants: Vec<VariantRecord>,
+}
+
+#[derive(Serialize)]
+pub struct CreatedLink {
+    pub id: String,
+    pub short_code: String,
+    pub short_url: String,
+    pub long_url: String,
+    pub delete_key: String,
+    pub reused: bool,
+}
+
+// A reused link answers 200 with the existing code; a new one answers 201.
+pub async fn create_link_api<P, Q>(
     State(state): State<AppState<P, Q>>,
+    caller: ApiCaller,
+    Json(body): Json<CreateLinkRequest>,
 ) -> impl IntoResponse
 where
     P: LinkPersistence + Send + Sync + 'static,
     Q: LinkQuery + Send + Sync + 'static,
 {
-    let short_url = match code.try_into() {
-        Ok(s) => s,
+    let parsed = parse_expiry(body.expires_at).and_then(|expires_at| {
+        let active_from = parse_activation(body.active_from)?;
+        let redirect_kind = parse_redirect(body.redirect_status)?;
+        let routing_rules = parse_rules(body.routing_rules)?;
+
+        Ok((active_from, expires_at, redirect_kind, routing_rules))
+    });
 
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Html("<h3>Invalid short code format.</h3>".to_string()),
+    let outcome = match parsed {
+        Ok((active_from, expires_at, redirect_kind, routing_rules)) => {
+            let request = NewLink {
+                url: body.url,
+                alias: body.alias,
+                active_from,
+                expires_at,
+                tags: body.tags,
+                always_preview: false,
+                redirect_kind,
+                passthrough: body.passthrough,
+                utm: body.utm.into(),
+                routing_rules,
+                variants: parse_variants(body.variants),
+                owner: caller.owner,
+                reuse_existing: body.reuse.unwrap_or(state.config.reuse_existing_links),
+            };
+
+            state.link_service.create_or_reuse(request).await
+        }
+        Err(e) => Err(e),
+    };
+
+    match outcome {
+        Ok((link, reused)) => {
+            let short_code = link.short_url().as_str().to_string();
+
+            let status = if reused {
+                StatusCode::OK
+            } else {
+                StatusCode::CREATED
+            };
+
+            (
+                status,
+                Json(CreatedLink {
+                    id: link.id().clone().into_inner().to_string(),
+                    short_url: state.config.short_link(&short_code),
+                    short_code,
+                    long_url: link.user_url().as_str().to_string(),
+                    delete_key: link.delete_hash_code().value().to_string(),
+                    reused,
+                }),
             )
                 .into_response()
         }
+
+        Err(e) => ApiError(e).into_response(),
+    }
+}
+
+// Permanent redirects may be cached so repeat visitors skip the server;
+// temporary ones must not be, and neither may a split, or clicks would go
+// uncounted and a later disable would not take effect.
+fn redirect_response(config: &AppConfig, target: &RedirectTarget) -> Response {
+    let kind = target
+        .link
+        .redirect_kind()
+        .unwrap_or(config.default_redirect);
+
+    let Ok(location) = HeaderValue::try_from(target.destination.as_str()) else {
+        return PageError(LinkError::ResponseFailure(format!(
+            "destination of {} is not a valid Location header",
+            target.link.short_url().as_str()
+        )))
+        .into_response();
     };
 
-    match state.link_service.redirect(short_url).await {
-        Ok(link) => Redirect::to(link.user_url().as_str()).into_response(),
+    // Rule-routed links answer differently per country, language or device,
+    // so like variant links they must not be cached by anyone.
+    let per_visitor = target.variant.is_some() || !target.link.routing_rules().is_empty();
 
-        Err(LinkError::NotFound) => (
-            StatusCode::NOT_FOUND,
-            Html("<h3>Link not found</h3>".to_string()),
+    let cache_control = if kind.is_permanent() && !per_visitor {
+        format!(
+            "public, max-age={}",
+            config.permanent_redirect_max_age.as_secs()
         )
-            .into_response(),
+    } else {
+        "private, no-store".to_string()
+    };
+
+    let mut response = (
+        StatusCode::from_u16(kind.status_code()).unwrap_or(StatusCode::FOUND),
+        [
+            (header::LOCATION, location),
+            (
+                header::CACHE_CONTROL,
+                HeaderValue::try_from(cache_control)
+                    .unwrap_or(HeaderValue::from_static("no-store")),
+            ),
+        ],
+    )
+        .into_response();
+
+    // Variant names and short codes are both cookie-safe by construction.
+    if let Some(variant) = &target.variant {
+        let code = target.link.short_url().as_str();
+        let cookie = format!(
+            "{}={variant}; Path=/l/{code}; Max-Age={VARIANT_COOKIE_MAX_AGE}; HttpOnly; SameSite=Lax",
+            variant_cookie(code)
+        );
+
+        if let Ok(value) = HeaderValue::try_from(cookie) {
+            response.headers_mut().append(header::SET_COOKIE, value);
+        }
+    }
+
+    response
+}
 
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Html("<h3>And internal error occurred.</h3>".to_string()),
+// Long enough to outlast a typical experiment.
+const VARIANT_COOKIE_MAX_AGE: u64 = 30 * 24 * 60 * 60;
+
+fn variant_cookie(code: &str) -> String {
+    format!("rl_variant_{code}")
+}
+
+pub fn cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|pair| {
+            let (key, value) = pair.trim().split_once('=')?;
+            (key == name).then(|| value.to_string())
+        })
+}
+
+// Neither answer may be cached, or visitors would keep seeing it after launch.
+fn coming_soon_response(
+    config: &AppConfig,
+    code: &ShortUrl,
+    active_from: DateTime<Utc>,
+) -> Response {
+    let mut response = match config
+        .coming_soon_url
+        .as_deref()
+        .and_then(|url| HeaderValue::try_from(url).ok())
+    {
+        Some(location) => (StatusCode::FOUND, [(header::LOCATION, location)]).into_response(),
+        None => render(
+            StatusCode::NOT_FOUND,
+            ComingSoonPage {
+                short_link: config.short_link(code.as_str()),
+                active_from: active_from.format("%B %-d, %Y at %H:%M UTC").to_string(),
+            },
+        ),
+    };
+
+    response.headers_mut().insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("private, no-store"),
+    );
+
+    response
+}
+
+// `X-Forwarded-For` is only believed behind a proxy we run, and then only
+// its last hop, which that proxy appended itself.
+pub fn client_ip(
+    config: &AppConfig,
+    headers: &HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+) -> Option<IpAddr> {
+    let forwarded = config
+        .trust_forwarded_for
+        .then(|| headers.get("x-forwarded-for"))
+        .flatten()
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next()?.trim().parse::<IpAddr>().ok());
+
+    forwarded.or(connect_info.map(|ConnectInfo(addr)| addr.ip()))
+}
+
+fn request_context(
+    config: &AppConfig,
+    code: &ShortUrl,
+    headers: &HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+) -> RequestContext {
+    let header_str = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+
+    RequestContext {
+        user_agent: header_str(header::USER_AGENT),
+        accept_language: header_str(header::ACCEPT_LANGUAGE),
+        client_ip: client_ip(config, headers, connect_info),
+        sticky_variant: cookie_value(headers, &variant_cookie(code.as_str())),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RedirectPath {
+    pub code: String,
+}
+
+// `/l/:code+` and `/l/:code?preview` show where a link goes instead of
+// following it. `/l/:code/*rest` serves passthrough links; the rest of the
+// path is taken from the raw URI so its percent-encoding survives.
+// `go` and `preview` steer this service, so they are not passed on to
+// the destination. Pairs are filtered raw to keep their encoding intact.
+fn forwarded_query(query: Option<&str>) -> Option<String> {
+    let kept: Vec<&str> = query?
+        .split('&')
+        .filter(|pair| {
+            let key = pair.split('=').next().unwrap_or_default();
+            !pair.is_empty() && key != "go" && key != "preview"
+        })
+        .collect();
+
+    (!kept.is_empty()).then(|| kept.join("&"))
+}
+
+// The preview's way out goes back through the short link so the visit is
+// counted and routed like any other.
+fn continue_link(short_link: &str, extra_path: Option<&str>, query: Option<&str>) -> String {
+    let path = extra_path
+        .filter(|extra| !extra.is_empty())
+        .map(|extra| format!("/{extra}"))
+        .unwrap_or_default();
+    let query = query.map(|query| format!("{query}&")).unwrap_or_default();
+
+    format!("{short_link}{path}?{query}go")
+}
+
+pub async fn redirect_link<P, Q>(
+    Path(RedirectPath { code }): Path<RedirectPath>,
+    Query(params): Query<HashMap<String, String>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    State(state): State<AppState<P, Q>>,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    // "", "l", code, rest
+    let extra_path = uri.path().splitn(4, '/').nth(3);
+
+    let wants_preview = code.ends_with('+') || params.contains_key("preview");
+    let code = code.trim_end_matches('+').to_string();
+
+    let Ok(short_url) = ShortUrl::try_from(code) else {
+        return PageError(LinkError::InvalidFormat).into_response();
+    };
+
+    // Only 307/308 carry a POST on to the destination; anything else would
+    // turn it into a GET there, so it is refused before a click is counted.
+    // Lookup failures are left to the visit below to report.
+    if method == Method::POST {
+        if let Ok(link) = state.link_service.find_by_code(short_url.clone()).await {
+            let kind = link
+                .redirect_kind()
+                .unwrap_or(state.config.default_redirect);
+
+            if !kind.preserves_method() {
+                return (
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    [(header::ALLOW, HeaderValue::from_static("GET"))],
+                )
+                    .into_response();
+            }
+        }
+    }
+
+    // "Continue" on a preview page comes back with `go`. A POST has nowhere
+    // to go from a preview page, so it is always followed.
+    let mode = if method == Method::POST {
+        VisitMode::Follow
+    } else if wants_preview {
+        VisitMode::Preview
+    } else if params.contains_key("go") {
+        VisitMode::Follow
+    } else if state.config.always_preview {
+        VisitMode::Preview
+    } else {
+        VisitMode::Auto
+    };
+    let query = forwarded_query(uri.query());
+
+    let outcome = state
+        .link_service
+        .visit(
+            short_url.clone(),
+            extra_path,
+            query.as_deref(),
+            &request_context(&state.config, &short_url, &headers, connect_info),
+            mode,
         )
-            .into_response(),
+        .await;
+
+    match outcome {
+        Ok(Visit::Redirect(target)) => redirect_response(&state.config, &target),
+
+        Ok(Visit::Preview(link, stats)) => {
+            let short_link = state.config.short_link(link.short_url().as_str());
+            let continue_link = continue_link(&short_link, extra_path, query.as_deref());
+
+            render(
+                StatusCode::OK,
+                PreviewPage::new(short_link, continue_link, &link, &stats),
+            )
+        }
+
+        // These pages explain more than the bare message, but keep the
+        // status the error maps to everywhere else.
+        Err(e) => match &e {
+            LinkError::NotYetActive(active_from) => {
+                coming_soon_response(&state.config, &short_url, *active_from)
+            }
+
+            LinkError::Disabled(reason) | LinkError::Blocked(reason) => render(
+                error_status(&e),
+                UnavailablePage {
+                    short_link: state.config.short_link(short_url.as_str()),
+                    blocked: matches!(e, LinkError::Blocked(_)),
+                    reason: reason.clone(),
+                },
+            ),
+
+            LinkError::ThreatDetected(threat_type) => {
+                let destination = match state.link_service.find_by_code(short_url.clone()).await {
+                    Ok(link) => link.user_url().as_str().to_string(),
+                    Err(_) => String::new(),
+                };
+
+                render(
+                    error_status(&e),
+                    WarningPage {
+                        short_link: state.config.short_link(short_url.as_str()),
+                        destination,
+                        threat_type: threat_type.clone(),
+                    },
+                )
+            }
+
+            _ => PageError(e).into_response(),
+        },
     }
 }
 
+// The key handed out when the link was created.
+#[derive(Deserialize)]
+pub struct DeleteLinkForm {
+    pub delete_key: String,
+}
+
 pub async fn delete_link<P, Q>(
     Path(id): Path<String>,
     State(state): State<AppState<P, Q>>,
+    CsrfForm(form): CsrfForm<DeleteLinkForm>,
 ) -> impl IntoResponse
 where
     P: LinkPersistence + Send + Sync + 'static,
@@ -107,33 +596,574 @@ where
 {
     let link_id = match LinkId::from_string(id) {
         Ok(id) => id,
+        Err(e) => return PageError(e).into_response(),
+    };
 
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Html("<h3>Invalid Link ID format.</h3>".to_string()),
-            )
-                .into_response()
+    let key = LinkKey::new(form.delete_key.trim().to_string());
+
+    match state.link_service.delete_with_key(link_id, key).await {
+        Ok(Some(link)) => render(
+            StatusCode::OK,
+            MessagePage {
+                heading: "Link removed".to_string(),
+                message: format!(
+                    "{} no longer leads anywhere.",
+                    state.config.short_link(link.short_url().as_str())
+                ),
+                warning: false,
+            },
+        ),
+        Ok(None) => PageError(LinkError::NotFound).into_response(),
+        Err(e) => PageError(e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BulkParams {
+    #[serde(default)]
+    pub mode: BulkMode,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkMode {
+    #[default]
+    Atomic,
+    BestEffort,
+}
+
+impl From<BulkMode> for BatchMode {
+    fn from(mode: BulkMode) -> Self {
+        match mode {
+            BulkMode::Atomic => BatchMode::Atomic,
+            BulkMode::BestEffort => BatchMode::BestEffort,
         }
+    }
+}
+
+#[derive(Deserialize)]
+struct BulkJsonRow {
+    url: String,
+    alias: Option<String>,
+    active_from: Option<String>,
+    expires_at: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    redirect_status: Option<u16>,
+    #[serde(default)]
+    passthrough: bool,
+    #[serde(default)]
+    utm: UtmFields,
+    #[serde(default)]
+    routing_rules: Vec<RuleRecord>,
+    #[serde(default)]
+    variants: Vec<VariantRecord>,
+}
+
+// CSV has no arrays, so tags arrive as a single `;`-separated cell.
+#[derive(Deserialize)]
+struct BulkCsvRow {
+    url: String,
+    alias: Option<String>,
+    active_from: Option<String>,
+    expires_at: Option<String>,
+    tags: Option<String>,
+    redirect_status: Option<u16>,
+    passthrough: Option<bool>,
+    utm_source: Option<String>,
+    utm_medium: Option<String>,
+    utm_campaign: Option<String>,
+    utm_term: Option<String>,
+    utm_content: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BulkReport {
+    pub mode: BulkMode,
+    pub created: usize,
+    pub failed: usize,
+    pub rows: Vec<BulkRowReport>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum BulkRowReport {
+    Created {
+        row: usize,
+        id: String,
+        short_code: String,
+        delete_key: String,
+    },
+    Failed {
+        row: usize,
+        error: String,
+        code: &'static str,
+    },
+}
+
+fn parse_timestamp(
+    raw: Option<String>,
+    invalid: LinkError,
+) -> Result<Option<DateTime<Utc>>, LinkError> {
+    match raw.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(value) => DateTime::parse_from_rfc3339(value)
+            .map(|dt| Some(dt.with_timezone(&Utc)))
+            .map_err(|_| invalid),
+    }
+}
+
+// Browsers submit `datetime-local` fields without an offset or seconds.
+fn parse_form_timestamp(
+    raw: Option<String>,
+    invalid: LinkError,
+) -> Result<Option<DateTime<Utc>>, LinkError> {
+    let naive = raw.as_deref().map(str::trim).and_then(|value| {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M")
+            .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+            .ok()
+    });
+
+    match naive {
+        Some(naive) => Ok(Some(naive.and_utc())),
+        None => parse_timestamp(raw, invalid),
+    }
+}
+
+fn parse_expiry(raw: Option<String>) -> Result<Option<DateTime<Utc>>, LinkError> {
+    parse_timestamp(raw, LinkError::InvalidExpiry)
+}
+
+fn parse_activation(raw: Option<String>) -> Result<Option<DateTime<Utc>>, LinkError> {
+    parse_timestamp(raw, LinkError::InvalidSchedule)
+}
+
+fn parse_redirect(raw: Option<u16>) -> Result<Option<RedirectKind>, LinkError> {
+    raw.map(RedirectKind::try_from).transpose()
+}
+
+fn parse_rules(records: Vec<RuleRecord>) -> Result<Vec<NewRoutingRule>, LinkError> {
+    records.into_iter().map(RuleRecord::into_new_rule).collect()
+}
+
+fn parse_variants(records: Vec<VariantRecord>) -> Vec<NewVariant> {
+    records
+        .into_iter()
+        .map(VariantRecord::into_new_variant)
+        .collect()
+}
+
+// Deserialises a JSON array, giving up as soon as it holds more than `max`
+// rows rather than after the whole body has been parsed.
+struct BoundedRows<'a> {
+    max: usize,
+    exceeded: &'a Cell<bool>,
+}
+
+impl<'de> DeserializeSeed<'de> for BoundedRows<'_> {
+    type Value = Vec<serde_json::Value>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for BoundedRows<'_> {
+    type Value = Vec<serde_json::Value>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an array of links")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut rows = Vec::new();
+
+        while let Some(row) = seq.next_element()? {
+            if rows.len() == self.max {
+                self.exceeded.set(true);
+                return Err(de::Error::custom("too many rows"));
+            }
+
+            rows.push(row);
+        }
+
+        Ok(rows)
+    }
+}
+
+fn parse_bulk_body(
+    headers: &HeaderMap,
+    body: &[u8],
+    owner: Option<String>,
+    max_rows: usize,
+) -> Result<Vec<Result<NewLink, LinkError>>, LinkError> {
+    let too_many_rows =
+        || LinkError::PayloadTooLarge(format!("A batch may contain at most {max_rows} rows"));
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    if content_type.starts_with("text/csv") {
+        csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(body)
+            .deserialize::<BulkCsvRow>()
+            .enumerate()
+            .map(|(index, row)| {
+                if index == max_rows {
+                    return Err(too_many_rows());
+                }
+
+                // A malformed record only fails its own row.
+                let row = match row {
+                    Ok(row) => row,
+                    Err(e) => return Ok(Err(LinkError::InvalidInput(format!("Invalid CSV: {e}")))),
+                };
+
+                Ok(parse_expiry(row.expires_at).and_then(|expires_at| {
+                    Ok(NewLink {
+                        url: row.url,
+                        alias: row.alias,
+                        active_from: parse_activation(row.active_from)?,
+                        expires_at,
+                        always_preview: false,
+                        redirect_kind: parse_redirect(row.redirect_status)?,
+                        passthrough: row.passthrough.unwrap_or_default(),
+                        utm: Utm {
+                            source: row.utm_source,
+                            medium: row.utm_medium,
+                            campaign: row.utm_campaign,
+                            term: row.utm_term,
+                            content: row.utm_content,
+                        },
+                        routing_rules: Vec::new(),
+                        variants: Vec::new(),
+                        tags: row
+                            .tags
+                            .unwrap_or_default()
+                            .split(';')
+                            .map(str::to_string)
+                            .collect(),
+                        owner: owner.clone(),
+                        reuse_existing: false,
+                    })
+                }))
+            })
+            .collect()
+    } else if content_type.starts_with("application/json") {
+        let exceeded = Cell::new(false);
+        let seed = BoundedRows {
+            max: max_rows,
+            exceeded: &exceeded,
+        };
+
+        let rows = seed
+            .deserialize(&mut serde_json::Deserializer::from_slice(body))
+            .map_err(|e| {
+                if exceeded.get() {
+                    too_many_rows()
+                } else {
+                    LinkError::InvalidInput(format!("Invalid JSON: {e}"))
+                }
+            })?;
+
+        // Only the array has to be well formed; an element of the wrong
+        // shape fails its own row.
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let row = serde_json::from_value::<BulkJsonRow>(row)
+                    .map_err(|e| LinkError::InvalidInput(format!("Invalid JSON: {e}")))?;
+
+                parse_expiry(row.expires_at).and_then(|expires_at| {
+                    Ok(NewLink {
+                        url: row.url,
+                        alias: row.alias,
+                        active_from: parse_activation(row.active_from)?,
+                        expires_at,
+                        tags: row.tags,
+                        always_preview: false,
+                        redirect_kind: parse_redirect(row.redirect_status)?,
+                        passthrough: row.passthrough,
+                        utm: row.utm.into(),
+                        routing_rules: parse_rules(row.routing_rules)?,
+                        variants: parse_variants(row.variants),
+                        owner: owner.clone(),
+                        reuse_existing: false,
+                    })
+                })
+            })
+            .collect())
+    } else {
+        Err(LinkError::UnsupportedMediaType(
+            "Content-Type must be text/csv or application/json".to_string(),
+        ))
+    }
+}
+
+pub async fn bulk_create_links<P, Q>(
+    State(state): State<AppState<P, Q>>,
+    Query(params): Query<BulkParams>,
+    caller: ApiCaller,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    // A batch can create hundreds of links in one request, so it is not
+    // open to anonymous callers.
+    let Some(owner) = caller.owner else {
+        return ApiError(LinkError::Unauthorized(
+            "An API key is required for bulk creation".to_string(),
+        ))
+        .into_response();
     };
 
-    match state.link_service.delete(link_id).await {
-        Ok(Some(_)) => (
-            StatusCode::OK,
-            Html("<p>Link deleted successfully.</p>".to_string()),
-        )
-            .into_response(),
+    let parsed = match parse_bulk_body(&headers, &body, Some(owner), state.config.bulk_max_rows) {
+        Ok(rows) => rows,
+        Err(e) => return ApiError(e).into_response(),
+    };
 
-        Ok(None) | Err(LinkError::NotFound) => (
-            StatusCode::NOT_FOUND,
-            Html("<p>Link not found</p>".to_string()),
-        )
-            .into_response(),
+    let mode = params.mode;
 
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Html("<h3> An internal error occurred.</h3>".to_string()),
-        )
-            .into_response(),
+    if matches!(mode, BulkMode::Atomic) && parsed.iter().any(Result::is_err) {
+        let rows = parsed
+            .into_iter()
+            .enumerate()
+            .map(|(index, row)| failed_row(index + 1, row.err().unwrap_or(LinkError::BatchAborted)))
+            .collect();
+
+        return bulk_response(mode, rows);
+    }
+
+    let requests: Vec<NewLink> = parsed.iter().filter_map(|row| row.clone().ok()).collect();
+
+    let mut outcome = match state.link_service.create_batch(requests, mode.into()).await {
+        Ok(outcome) => outcome.into_iter(),
+        Err(e) => return ApiError(e).into_response(),
+    };
+
+    // Rows that failed to parse never reach the service, so results are
+    // stitched back into their original positions here.
+    let rows = parsed
+        .into_iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let result =
+                row.and_then(|_| outcome.next().unwrap_or(Err(LinkError::LinkCreationError)));
+
+            match result {
+                Ok(link) => created_row(index + 1, link),
+                Err(e) => failed_row(index + 1, e),
+            }
+        })
+        .collect();
+
+    bulk_response(mode, rows)
+}
+
+fn created_row(row: usize, link: Link) -> BulkRowReport {
+    BulkRowReport::Created {
+        row,
+        id: link.id().clone().into_inner().to_string(),
+        short_code: link.short_url().as_str().to_string(),
+        delete_key: link.delete_hash_code().value().to_string(),
+    }
+}
+
+fn failed_row(row: usize, error: LinkError) -> BulkRowReport {
+    BulkRowReport::Failed {
+        row,
+        error: public_message(&error),
+        code: error.code(),
+    }
+}
+
+fn bulk_response(mode: BulkMode, rows: Vec<BulkRowReport>) -> axum::response::Response {
+    let created = rows
+        .iter()
+        .filter(|row| matches!(row, BulkRowReport::Created { .. }))
+        .count();
+    let failed = rows.len() - created;
+
+    let status = match (failed, mode) {
+        (0, _) => StatusCode::CREATED,
+        (_, BulkMode::Atomic) => StatusCode::UNPROCESSABLE_ENTITY,
+        (_, BulkMode::BestEffort) => StatusCode::MULTI_STATUS,
+    };
+
+    (
+        status,
+        Json(BulkReport {
+            mode,
+            created,
+            failed,
+            rows,
+        }),
+    )
+        .into_response()
+}
+
+#[derive(Serialize)]
+pub struct LinkMetadata {
+    pub short_code: String,
+    pub short_url: String,
+    pub long_url: String,
+    pub status: &'static str,
+    pub created_at: String,
+    pub active_from: Option<String>,
+    pub expires_at: Option<String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct VariantCount {
+    pub variant: String,
+    pub clicks: i64,
+}
+
+#[derive(Serialize)]
+pub struct LinkStatsBody {
+    pub total: i64,
+    pub first_clicked_at: Option<String>,
+    pub last_clicked_at: Option<String>,
+    pub by_variant: Vec<VariantCount>,
+}
+
+// Both answers change whenever the link does, so clients revalidate every
+// time; an unchanged answer then costs a 304 instead of the body. They are
+// per owner, so shared caches keep out.
+const READ_CACHE_CONTROL: &str = "private, no-cache";
+
+fn api_time(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+// OWASP A01 Broken Access Control: only the key that owns a link may read
+// it. Anyone else is told it does not exist, so codes cannot be probed.
+async fn owned_link<P, Q>(
+    state: &AppState<P, Q>,
+    caller: &ApiCaller,
+    code: String,
+) -> Result<Link, ApiError>
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let Some(owner) = caller.owner.as_deref() else {
+        return Err(ApiError(LinkError::Unauthorized(
+            "An API key is required to read a link".to_string(),
+        )));
+    };
+
+    let short_url = ShortUrl::try_from(code).map_err(|_| ApiError(LinkError::InvalidFormat))?;
+    let link = state.link_service.find_by_code(short_url).await?;
+
+    if link.owner() != Some(owner) {
+        return Err(ApiError(LinkError::NotFound));
     }
+
+    Ok(link)
+}
+
+fn json_read<T: Serialize>(
+    headers: &HeaderMap,
+    value: &T,
+    last_modified: Option<DateTime<Utc>>,
+) -> Response {
+    match serde_json::to_vec(value) {
+        Ok(body) => conditional_response(
+            headers,
+            "application/json",
+            READ_CACHE_CONTROL,
+            last_modified,
+            body,
+        ),
+        Err(e) => ApiError(LinkError::ResponseFailure(format!("JSON body: {e}"))).into_response(),
+    }
+}
+
+pub async fn link_metadata<P, Q>(
+    State(state): State<AppState<P, Q>>,
+    Path(code): Path<String>,
+    caller: ApiCaller,
+    headers: HeaderMap,
+) -> Response
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let link = match owned_link(&state, &caller, code).await {
+        Ok(link) => link,
+        Err(e) => return e.into_response(),
+    };
+
+    let short_code = link.short_url().as_str().to_string();
+    let metadata = LinkMetadata {
+        short_url: state.config.short_link(&short_code),
+        short_code,
+        long_url: link.user_url().as_str().to_string(),
+        status: link.status().as_str(),
+        created_at: api_time(link.clone().created_at().into_inner()),
+        active_from: link
+            .active_from()
+            .map(|active_from| api_time(active_from.clone().into_inner())),
+        expires_at: link
+            .expires_at()
+            .map(|expires_at| api_time(expires_at.clone().into_inner())),
+        tags: link
+            .tags()
+            .iter()
+            .map(|tag| tag.as_str().to_string())
+            .collect(),
+    };
+
+    // A status change leaves no timestamp behind, so the ETag, which clients
+    // send first, is what catches it; Last-Modified is the creation time.
+    let created_at = link.created_at().into_inner();
+    json_read(&headers, &metadata, Some(created_at))
+}
+
+pub async fn link_stats<P, Q>(
+    State(state): State<AppState<P, Q>>,
+    Path(code): Path<String>,
+    caller: ApiCaller,
+    headers: HeaderMap,
+) -> Response
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let link = match owned_link(&state, &caller, code).await {
+        Ok(link) => link,
+        Err(e) => return e.into_response(),
+    };
+
+    let stats = match state.link_service.stats(link.id().clone()).await {
+        Ok(stats) => stats,
+        Err(e) => return ApiError(e).into_response(),
+    };
+
+    let last_modified = stats
+        .last_clicked_at
+        .unwrap_or_else(|| link.clone().created_at().into_inner());
+    let body = LinkStatsBody {
+        total: stats.total,
+        first_clicked_at: stats.first_clicked_at.map(api_time),
+        last_clicked_at: stats.last_clicked_at.map(api_time),
+        by_variant: stats
+            .by_variant
+            .into_iter()
+            .map(|row| VariantCount {
+                variant: row.variant,
+                clicks: row.clicks,
+            })
+            .collect(),
+    };
+
+    json_read(&headers, &body, Some(last_modified))
 }
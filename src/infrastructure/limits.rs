@@ -0,0 +1,45 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::domain::errors::LinkError;
+use crate::infrastructure::config::AppConfig;
+use crate::infrastructure::errors::ApiError;
+
+// Covers reading the body and running the handler, so a client trickling
+// its upload cannot hold a connection open indefinitely. Database stalls
+// hit the shorter statement timeout first and come back as 503 instead.
+pub async fn request_timeout(
+    State(config): State<Arc<AppConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    within(config.request_timeout, request, next).await
+}
+
+// Exports stream after the headers are sent and are not limited past that
+// point; imports have to be read whole, hence the longer allowance.
+pub async fn admin_request_timeout(
+    State(config): State<Arc<AppConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    within(config.admin_request_timeout, request, next).await
+}
+
+// Running out of time is a 408. A stalled database never gets this far:
+// its statement timeout answers 503 with Retry-After first.
+async fn within(limit: Duration, request: Request, next: Next) -> Response {
+    match tokio::time::timeout(limit, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            tracing::warn!("request timed out after {limit:?}");
+            ApiError(LinkError::TimedOut).into_response()
+        }
+    }
+}
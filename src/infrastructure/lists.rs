@@ -0,0 +1,90 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::application::{policy::DomainPolicy, service::LinkService, threat::ThreatList};
+use crate::domain::ports::{LinkPersistence, LinkQuery};
+use crate::infrastructure::config::AppConfig;
+
+pub fn load_domain_policy(path: &Path) -> Result<DomainPolicy, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+
+    DomainPolicy::parse(&source).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+pub fn load_threat_list(path: &Path) -> Result<ThreatList, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+
+    ThreatList::parse(&source).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+// Both files are read and parsed before either is swapped in, so a typo in
+// one leaves both previous lists in force: a bad file never opens the gates,
+// and the two never come from different edits.
+pub fn reload_lists<P, Q>(service: &LinkService<P, Q>, config: &AppConfig) -> Result<(), String>
+where
+    P: LinkPersistence + Send + Sync,
+    Q: LinkQuery + Send + Sync,
+{
+    let policy = config
+        .domain_policy_file
+        .as_deref()
+        .map(|path| load_domain_policy(path).map(|policy| (path, policy)))
+        .transpose()?;
+
+    let threats = config
+        .threat_list_file
+        .as_deref()
+        .map(|path| load_threat_list(path).map(|list| (path, list)))
+        .transpose()?;
+
+    if let Some((path, policy)) = policy {
+        service.set_domain_policy(policy);
+        tracing::info!("loaded domain policy from {}", path.display());
+    }
+
+    if let Some((path, list)) = threats {
+        tracing::info!(
+            "loaded {} threat prefixes from {}",
+            list.len(),
+            path.display()
+        );
+        service.set_threat_list(list);
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn reload_on_sighup<P, Q>(service: Arc<LinkService<P, Q>>, config: Arc<AppConfig>)
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    use tokio::signal::unix::{signal, SignalKind};
+
+    tokio::spawn(async move {
+        let mut hangups = match signal(SignalKind::hangup()) {
+            Ok(hangups) => hangups,
+            Err(e) => {
+                tracing::warn!("cannot listen for SIGHUP: {e}");
+                return;
+            }
+        };
+
+        while hangups.recv().await.is_some() {
+            if let Err(e) = reload_lists(&service, &config) {
+                tracing::error!("reload failed: {e}");
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+pub fn reload_on_sighup<P, Q>(_service: Arc<LinkService<P, Q>>, _config: Arc<AppConfig>)
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+}
@@ -1,5 +1,39 @@
+pub mod admin;
+
+pub mod auth;
+
+pub mod caching;
+
+pub mod config;
+
+pub mod csrf;
+
+pub mod dashboard;
+
+pub mod errors;
+
+pub mod geoip;
+
 pub mod handlers;
 
+pub mod limits;
+
+pub mod lists;
+
+pub mod pages;
+
+pub mod qr;
+
+pub mod reports;
+
 pub mod repository;
 
-pub mod routes;
\ No newline at end of file
+pub mod resolver;
+
+pub mod routes;
+
+pub mod rules;
+
+pub mod security;
+
+pub mod transfer;
@@ -0,0 +1,308 @@
+use askama::Template;
+use axum::{
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::domain::{
+    click::{ClickStats, DailyClicks, VariantClicks},
+    link::{Link, LinkStatus},
+    report::{Report, ReportState},
+};
+
+#[derive(Template)]
+#[template(path = "preview.html")]
+pub struct PreviewPage {
+    pub short_link: String,
+    pub continue_link: String,
+    pub destination: String,
+    pub domain: String,
+    pub created_at: String,
+    pub clicks: i64,
+    pub flagged: bool,
+}
+
+impl PreviewPage {
+    pub fn new(short_link: String, continue_link: String, link: &Link, stats: &ClickStats) -> Self {
+        let destination = link.user_url().as_str().to_string();
+        let domain = url::Url::parse(&destination)
+            .ok()
+            .and_then(|parsed| parsed.host_str().map(str::to_string))
+            .unwrap_or_default();
+
+        Self {
+            short_link,
+            continue_link,
+            destination,
+            domain,
+            created_at: link
+                .clone()
+                .created_at()
+                .into_inner()
+                .format("%B %-d, %Y")
+                .to_string(),
+            clicks: stats.total,
+            flagged: link.always_preview(),
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "warning.html")]
+pub struct WarningPage {
+    pub short_link: String,
+    pub destination: String,
+    pub threat_type: String,
+}
+
+#[derive(Template)]
+#[template(path = "coming_soon.html")]
+pub struct ComingSoonPage {
+    pub short_link: String,
+    pub active_from: String,
+}
+
+#[derive(Template)]
+#[template(path = "unavailable.html")]
+pub struct UnavailablePage {
+    pub short_link: String,
+    pub blocked: bool,
+    pub reason: Option<String>,
+}
+
+// Error pages, and the few plain confirmations that need nothing more.
+#[derive(Template)]
+#[template(path = "message.html")]
+pub struct MessagePage {
+    pub heading: String,
+    pub message: String,
+    pub warning: bool,
+}
+
+#[derive(Template)]
+#[template(path = "report.html")]
+pub struct ReportPage {
+    pub csrf_token: String,
+    pub short_link: String,
+    pub reasons: Vec<&'static str>,
+    pub max_details: usize,
+    pub submitted: bool,
+    pub error: Option<String>,
+}
+
+// What a successful submission of the home page form shows.
+pub struct CreatedResult {
+    pub id: String,
+    pub short_link: String,
+    pub short_code: String,
+    pub delete_key: String,
+}
+
+// Field values are echoed back so a rejected form does not have to be
+// retyped.
+#[derive(Template, Default)]
+#[template(path = "home.html")]
+pub struct HomePage {
+    pub csrf_token: String,
+    pub long_url: String,
+    pub alias: String,
+    pub expires_at: String,
+    pub result: Option<CreatedResult>,
+    pub error: Option<String>,
+}
+
+// One row of the admin link table.
+pub struct LinkSummary {
+    pub id: String,
+    pub short_code: String,
+    pub destination: String,
+    pub status: &'static str,
+    pub created_at: String,
+}
+
+impl From<&Link> for LinkSummary {
+    fn from(link: &Link) -> Self {
+        Self {
+            id: link.id().clone().into_inner().to_string(),
+            short_code: link.short_url().as_str().to_string(),
+            destination: link.user_url().as_str().to_string(),
+            status: link.status().as_str(),
+            created_at: admin_time(link.clone().created_at().into_inner()),
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "admin/dashboard.html")]
+pub struct DashboardPage {
+    pub query: String,
+    pub links: Vec<LinkSummary>,
+    pub previous_page: Option<String>,
+    pub next_page: Option<String>,
+}
+
+// A bar of the click chart, already laid out in SVG user units.
+pub struct ChartBar {
+    pub label: String,
+    pub clicks: i64,
+    pub x: usize,
+    pub y: i64,
+    pub height: i64,
+}
+
+pub const CHART_HEIGHT: i64 = 100;
+pub const CHART_BAR_WIDTH: usize = 10;
+
+pub fn chart_bars(days: &[DailyClicks]) -> Vec<ChartBar> {
+    let busiest = days.iter().map(|day| day.clicks).max().unwrap_or(0).max(1);
+
+    days.iter()
+        .enumerate()
+        .map(|(index, day)| {
+            // A single click still gets a visible sliver.
+            let height = match day.clicks {
+                0 => 0,
+                clicks => (clicks * CHART_HEIGHT / busiest).max(1),
+            };
+
+            ChartBar {
+                label: day.day.format("%b %-d").to_string(),
+                clicks: day.clicks,
+                x: index * CHART_BAR_WIDTH,
+                y: CHART_HEIGHT - height,
+                height,
+            }
+        })
+        .collect()
+}
+
+#[derive(Template)]
+#[template(path = "admin/link.html")]
+pub struct LinkDetailPage {
+    pub csrf_token: String,
+    pub id: String,
+    pub short_link: String,
+    pub destination: String,
+    pub status: &'static str,
+    pub status_reason: Option<String>,
+    pub created_at: String,
+    pub active_from: Option<String>,
+    pub expires_at: Option<String>,
+    pub owner: Option<String>,
+    pub tags: Vec<String>,
+    pub total_clicks: i64,
+    pub last_clicked_at: Option<String>,
+    pub chart: Vec<ChartBar>,
+    pub chart_width: usize,
+    pub chart_height: i64,
+    pub by_variant: Vec<VariantClicks>,
+    pub can_disable: bool,
+    pub can_block: bool,
+    pub can_enable: bool,
+}
+
+impl LinkDetailPage {
+    pub fn new(
+        csrf_token: String,
+        short_link: String,
+        link: &Link,
+        stats: ClickStats,
+        days: &[DailyClicks],
+    ) -> Self {
+        let status = link.status();
+
+        Self {
+            csrf_token,
+            id: link.id().clone().into_inner().to_string(),
+            short_link,
+            destination: link.user_url().as_str().to_string(),
+            status: status.as_str(),
+            status_reason: link.status_reason().map(str::to_string),
+            created_at: admin_time(link.clone().created_at().into_inner()),
+            active_from: link
+                .active_from()
+                .map(|active_from| admin_time(active_from.clone().into_inner())),
+            expires_at: link
+                .expires_at()
+                .map(|expires_at| admin_time(expires_at.clone().into_inner())),
+            owner: link.owner().map(str::to_string),
+            tags: link
+                .tags()
+                .iter()
+                .map(|tag| tag.as_str().to_string())
+                .collect(),
+            total_clicks: stats.total,
+            last_clicked_at: stats.last_clicked_at.map(admin_time),
+            chart: chart_bars(days),
+            chart_width: days.len().max(1) * CHART_BAR_WIDTH,
+            chart_height: CHART_HEIGHT,
+            by_variant: stats.by_variant,
+            can_disable: status.can_become(LinkStatus::Disabled),
+            can_block: status.can_become(LinkStatus::Blocked),
+            can_enable: status.can_become(LinkStatus::Active),
+        }
+    }
+}
+
+pub struct ReportSummary {
+    pub id: String,
+    pub link_id: String,
+    pub reason: &'static str,
+    pub details: Option<String>,
+    pub state: &'static str,
+    pub open: bool,
+    pub created_at: String,
+}
+
+impl From<&Report> for ReportSummary {
+    fn from(report: &Report) -> Self {
+        Self {
+            id: report.id().to_string(),
+            link_id: report.link_id().clone().into_inner().to_string(),
+            reason: report.reason().as_str(),
+            details: report.details().map(str::to_string),
+            state: report.state().as_str(),
+            open: report.state() == ReportState::Open,
+            created_at: admin_time(report.created_at()),
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "admin/reports.html")]
+pub struct ReportQueuePage {
+    pub csrf_token: String,
+    // Each state filter, and whether it is the one shown.
+    pub filters: Vec<(&'static str, bool)>,
+    pub reports: Vec<ReportSummary>,
+}
+
+// Shown when an admin action fails; `back` is where to return to.
+#[derive(Template)]
+#[template(path = "admin/message.html")]
+pub struct AdminMessagePage {
+    pub title: String,
+    pub message: String,
+    pub back: String,
+}
+
+fn admin_time(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+// Rendering only fails on a template bug, which should still yield a page.
+pub fn render<T: Template>(status: StatusCode, page: T) -> Response {
+    match page.render() {
+        Ok(html) => (status, Html(html)).into_response(),
+        Err(e) => {
+            tracing::error!("failed to render template: {e}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Html("<h3>An internal error occurred.</h3>".to_string()),
+            )
+                .into_response()
+        }
+    }
+}
@@ -0,0 +1,442 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Mutex, PoisonError},
+};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::IntoResponse,
+};
+use qrcode::{Color, EcLevel, QrCode};
+use serde::Deserialize;
+
+use crate::domain::{
+    errors::LinkError,
+    link::ShortUrl,
+    ports::{LinkPersistence, LinkQuery},
+};
+use crate::infrastructure::{caching::conditional_response, errors::PageError, handlers::AppState};
+
+// The image itself never changes, but the link behind it can be disabled or
+// blocked at any time; revalidating every fetch lets a takedown reach
+// scanners of printed codes, and an unchanged image costs only a 304.
+const QR_CACHE_CONTROL: &str = "no-cache";
+
+// Largest image edge in pixels; anything bigger is better served as SVG.
+const MAX_SIZE: u32 = 1024;
+
+// Rendered images kept in memory, oldest dropped first. A 1024px PNG is a
+// few tens of kilobytes, so the cache stays within a few megabytes.
+const CACHED_RENDERS: usize = 128;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QrFormat {
+    #[default]
+    Png,
+    Svg,
+}
+
+impl QrFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Svg => "image/svg+xml",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+pub enum QrEcc {
+    L,
+    #[default]
+    M,
+    Q,
+    H,
+}
+
+impl From<QrEcc> for EcLevel {
+    fn from(ecc: QrEcc) -> Self {
+        match ecc {
+            QrEcc::L => EcLevel::L,
+            QrEcc::M => EcLevel::M,
+            QrEcc::Q => EcLevel::Q,
+            QrEcc::H => EcLevel::H,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct QrParams {
+    #[serde(default)]
+    pub format: QrFormat,
+    pub size: Option<u32>,
+    pub margin: Option<u32>,
+    #[serde(default)]
+    pub ecc: QrEcc,
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb(u8, u8, u8);
+
+impl Rgb {
+    // Accepts `rrggbb` with or without a leading `#`.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let hex = raw.trim().trim_start_matches('#');
+
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let channel = |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).ok();
+
+        Some(Self(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct QrStyle {
+    pub size: u32,
+    pub margin: u32,
+    pub fg: Rgb,
+    pub bg: Rgb,
+}
+
+impl QrStyle {
+    fn from_params(params: &QrParams) -> Result<Self, &'static str> {
+        let size = params.size.unwrap_or(256);
+        let margin = params.margin.unwrap_or(4);
+
+        if !(64..=MAX_SIZE).contains(&size) {
+            return Err("size must be between 64 and 1024");
+        }
+
+        if margin > 16 {
+            return Err("margin must be at most 16 modules");
+        }
+
+        let colour = |raw: &Option<String>, default: Rgb| match raw {
+            Some(value) => Rgb::parse(value).ok_or("colours must be six hex digits"),
+            None => Ok(default),
+        };
+
+        Ok(Self {
+            size,
+            margin,
+            fg: colour(&params.fg, Rgb(0, 0, 0))?,
+            bg: colour(&params.bg, Rgb(255, 255, 255))?,
+        })
+    }
+}
+
+pub fn render_svg(code: &QrCode, style: &QrStyle) -> String {
+    let width = code.width();
+    let total = width as u32 + 2 * style.margin;
+    let modules = code.to_colors();
+
+    let mut path = String::new();
+    for (index, colour) in modules.iter().enumerate() {
+        if *colour == Color::Dark {
+            let x = (index % width) as u32 + style.margin;
+            let y = (index / width) as u32 + style.margin;
+            path.push_str(&format!("M{x} {y}h1v1h-1z"));
+        }
+    }
+
+    format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" "#,
+            r#"viewBox="0 0 {total} {total}" shape-rendering="crispEdges">"#,
+            r#"<rect width="{total}" height="{total}" fill="{bg}"/>"#,
+            r#"<path d="{path}" fill="{fg}"/></svg>"#
+        ),
+        size = style.size,
+        total = total,
+        bg = style.bg.to_hex(),
+        fg = style.fg.to_hex(),
+        path = path,
+    )
+}
+
+// Modules are scaled by a whole number of pixels so edges stay sharp; the
+// image is therefore at most `size` pixels wide.
+pub fn render_png(code: &QrCode, style: &QrStyle) -> Result<Vec<u8>, png::EncodingError> {
+    let width = code.width() as u32;
+    let total = width + 2 * style.margin;
+    let scale = (style.size / total).max(1);
+    let pixels = total * scale;
+    let modules = code.to_colors();
+
+    let mut data = Vec::with_capacity((pixels * pixels * 3) as usize);
+    for py in 0..pixels {
+        for px in 0..pixels {
+            let mx = (px / scale).checked_sub(style.margin);
+            let my = (py / scale).checked_sub(style.margin);
+
+            let dark = match (mx, my) {
+                (Some(x), Some(y)) if x < width && y < width => {
+                    modules[(y * width + x) as usize] == Color::Dark
+                }
+                _ => false,
+            };
+
+            let Rgb(r, g, b) = if dark { style.fg } else { style.bg };
+            data.extend_from_slice(&[r, g, b]);
+        }
+    }
+
+    let mut out = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut out, pixels, pixels);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+    }
+
+    Ok(out)
+}
+
+// Everything the rendered bytes depend on. The link's status is not part of
+// it: that is checked on every request before the cache is consulted.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct RenderKey {
+    target: String,
+    format: QrFormat,
+    ecc: QrEcc,
+    style: QrStyle,
+}
+
+#[derive(Default)]
+struct Renders {
+    images: HashMap<RenderKey, Vec<u8>>,
+    order: VecDeque<RenderKey>,
+}
+
+// Printed codes are fetched over and over with the same parameters, so the
+// encoded image is kept rather than redrawn pixel by pixel each time.
+pub struct QrCache {
+    capacity: usize,
+    renders: Mutex<Renders>,
+}
+
+impl Default for QrCache {
+    fn default() -> Self {
+        Self::new(CACHED_RENDERS)
+    }
+}
+
+impl QrCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            renders: Mutex::new(Renders::default()),
+        }
+    }
+
+    fn get(&self, key: &RenderKey) -> Option<Vec<u8>> {
+        self.renders
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .images
+            .get(key)
+            .cloned()
+    }
+
+    fn insert(&self, key: RenderKey, image: Vec<u8>) {
+        let mut renders = self.renders.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if self.capacity == 0 || renders.images.contains_key(&key) {
+            return;
+        }
+
+        while renders.order.len() >= self.capacity {
+            if let Some(oldest) = renders.order.pop_front() {
+                renders.images.remove(&oldest);
+            }
+        }
+
+        renders.order.push_back(key.clone());
+        renders.images.insert(key, image);
+    }
+}
+
+fn render_image(key: &RenderKey) -> Result<Vec<u8>, LinkError> {
+    let qr = QrCode::with_error_correction_level(key.target.as_bytes(), key.ecc.into()).map_err(
+        |_| {
+            LinkError::InvalidInput(
+                "The short URL does not fit in a QR code at this error correction level"
+                    .to_string(),
+            )
+        },
+    )?;
+
+    match key.format {
+        QrFormat::Svg => Ok(render_svg(&qr, &key.style).into_bytes()),
+        QrFormat::Png => render_png(&qr, &key.style)
+            .map_err(|e| LinkError::ResponseFailure(format!("QR code PNG: {e}"))),
+    }
+}
+
+pub async fn qr_code<P, Q>(
+    Path(code): Path<String>,
+    Query(params): Query<QrParams>,
+    State(state): State<AppState<P, Q>>,
+    headers: HeaderMap,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let Ok(short_url) = ShortUrl::try_from(code) else {
+        return PageError(LinkError::InvalidFormat).into_response();
+    };
+
+    let style = match QrStyle::from_params(&params) {
+        Ok(style) => style,
+        Err(message) => {
+            return PageError(LinkError::InvalidInput(message.to_string())).into_response()
+        }
+    };
+
+    let link = match state.link_service.printable(short_url).await {
+        Ok(link) => link,
+        Err(e) => return PageError(e).into_response(),
+    };
+
+    let key = RenderKey {
+        target: state.config.short_link(link.short_url().as_str()),
+        format: params.format,
+        ecc: params.ecc,
+        style,
+    };
+
+    let image = match state.qr_cache.get(&key) {
+        Some(image) => image,
+        None => match render_image(&key) {
+            Ok(image) => {
+                state.qr_cache.insert(key, image.clone());
+                image
+            }
+            Err(e) => return PageError(e).into_response(),
+        },
+    };
+
+    conditional_response(
+        &headers,
+        params.format.content_type(),
+        QR_CACHE_CONTROL,
+        None,
+        image,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(query: &str) -> QrParams {
+        serde_urlencoded::from_str(query).unwrap()
+    }
+
+    fn style(query: &str) -> Result<QrStyle, &'static str> {
+        QrStyle::from_params(&params(query))
+    }
+
+    fn key(format: QrFormat) -> RenderKey {
+        RenderKey {
+            target: "https://sho.rt/l/launch".to_string(),
+            format,
+            ecc: QrEcc::M,
+            style: style("").unwrap(),
+        }
+    }
+
+    #[test]
+    fn defaults_apply_without_parameters() {
+        let parsed = params("");
+
+        assert_eq!(parsed.format, QrFormat::Png);
+        assert_eq!(parsed.ecc, QrEcc::M);
+        assert_eq!(
+            style("").unwrap(),
+            QrStyle {
+                size: 256,
+                margin: 4,
+                fg: Rgb(0, 0, 0),
+                bg: Rgb(255, 255, 255),
+            }
+        );
+    }
+
+    #[test]
+    fn size_and_margin_are_bounded() {
+        assert_eq!(style("size=64").unwrap().size, 64);
+        assert_eq!(style("size=1024").unwrap().size, 1024);
+        assert!(style("size=63").is_err());
+        assert!(style("size=1025").is_err());
+
+        assert_eq!(style("margin=0").unwrap().margin, 0);
+        assert_eq!(style("margin=16").unwrap().margin, 16);
+        assert!(style("margin=17").is_err());
+    }
+
+    #[test]
+    fn ec_levels_map_onto_the_encoder() {
+        for (raw, level) in [
+            ("L", EcLevel::L),
+            ("M", EcLevel::M),
+            ("Q", EcLevel::Q),
+            ("H", EcLevel::H),
+        ] {
+            assert_eq!(EcLevel::from(params(&format!("ecc={raw}")).ecc), level);
+        }
+
+        assert!(serde_urlencoded::from_str::<QrParams>("ecc=X").is_err());
+    }
+
+    #[test]
+    fn colours_accept_six_hex_digits() {
+        assert_eq!(Rgb::parse("#1a2B3c"), Some(Rgb(0x1a, 0x2b, 0x3c)));
+        assert_eq!(Rgb::parse("ffffff"), Some(Rgb(255, 255, 255)));
+        assert_eq!(Rgb::parse("#fff"), None);
+        assert_eq!(Rgb::parse("gg0000"), None);
+
+        let styled = style("fg=%23ff0000&bg=00ff00").unwrap();
+        assert_eq!(styled.fg, Rgb(255, 0, 0));
+        assert_eq!(styled.bg, Rgb(0, 255, 0));
+        assert!(style("fg=red").is_err());
+    }
+
+    #[test]
+    fn formats_carry_their_content_type() {
+        assert_eq!(params("format=svg").format.content_type(), "image/svg+xml");
+        assert_eq!(params("format=png").format.content_type(), "image/png");
+
+        let svg = render_image(&key(QrFormat::Svg)).unwrap();
+        assert!(svg.starts_with(b"<svg xmlns=\"http://www.w3.org/2000/svg\""));
+
+        let png = render_image(&key(QrFormat::Png)).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    }
+
+    #[test]
+    fn cache_drops_the_oldest_render() {
+        let cache = QrCache::new(1);
+
+        cache.insert(key(QrFormat::Png), vec![1]);
+        assert_eq!(cache.get(&key(QrFormat::Png)), Some(vec![1]));
+
+        cache.insert(key(QrFormat::Svg), vec![2]);
+        assert_eq!(cache.get(&key(QrFormat::Png)), None);
+        assert_eq!(cache.get(&key(QrFormat::Svg)), Some(vec![2]));
+    }
+}
@@ -0,0 +1,153 @@
+use axum::{
+    extract::{ConnectInfo, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::net::SocketAddr;
+
+use crate::application::command::{NewReport, ReportOutcome};
+use crate::domain::{
+    errors::LinkError,
+    link::ShortUrl,
+    ports::{LinkPersistence, LinkQuery},
+    report::{ReportReason, MAX_REPORT_DETAILS_LEN},
+};
+use crate::infrastructure::csrf::{CsrfForm, CsrfToken};
+use crate::infrastructure::errors::{error_status, ApiError, PageError};
+use crate::infrastructure::handlers::{client_ip, AppState};
+use crate::infrastructure::pages::{render, ReportPage};
+
+#[derive(Deserialize)]
+pub struct ReportRequest {
+    pub reason: String,
+    pub details: Option<String>,
+}
+
+fn report_page(
+    token: &CsrfToken,
+    short_link: String,
+    submitted: bool,
+    error: Option<String>,
+) -> ReportPage {
+    ReportPage {
+        csrf_token: token.value().to_string(),
+        short_link,
+        reasons: ReportReason::ALL.iter().map(ReportReason::as_str).collect(),
+        max_details: MAX_REPORT_DETAILS_LEN,
+        submitted,
+        error,
+    }
+}
+
+fn invalid_code() -> Response {
+    PageError(LinkError::InvalidFormat).into_response()
+}
+
+pub async fn report_form<P, Q>(
+    Path(code): Path<String>,
+    headers: HeaderMap,
+    State(state): State<AppState<P, Q>>,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let Ok(short_url) = ShortUrl::try_from(code) else {
+        return invalid_code();
+    };
+
+    let token = CsrfToken::for_request(&headers);
+
+    match state.link_service.find_by_code(short_url.clone()).await {
+        Ok(_) => token.attach(
+            &state.config,
+            render(
+                StatusCode::OK,
+                report_page(
+                    &token,
+                    state.config.short_link(short_url.as_str()),
+                    false,
+                    None,
+                ),
+            ),
+        ),
+        Err(e) => PageError(e).into_response(),
+    }
+}
+
+// A repeat report gets the same thank-you page as the first one.
+pub async fn submit_report_form<P, Q>(
+    Path(code): Path<String>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    State(state): State<AppState<P, Q>>,
+    CsrfForm(form): CsrfForm<ReportRequest>,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let Ok(short_url) = ShortUrl::try_from(code) else {
+        return invalid_code();
+    };
+
+    let short_link = state.config.short_link(short_url.as_str());
+    let token = CsrfToken::for_request(&headers);
+    let request = NewReport {
+        reason: form.reason,
+        details: form.details,
+        reporter_ip: client_ip(&state.config, &headers, connect_info),
+    };
+
+    match state.link_service.report(short_url, request).await {
+        Ok(_) => render(StatusCode::OK, report_page(&token, short_link, true, None)),
+
+        Err(e @ LinkError::InvalidReport(_)) => render(
+            error_status(&e),
+            report_page(&token, short_link, false, Some(e.to_string())),
+        ),
+
+        Err(e) => PageError(e).into_response(),
+    }
+}
+
+pub async fn report_link_api<P, Q>(
+    Path(code): Path<String>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    State(state): State<AppState<P, Q>>,
+    Json(body): Json<ReportRequest>,
+) -> impl IntoResponse
+where
+    P: LinkPersistence + Send + Sync + 'static,
+    Q: LinkQuery + Send + Sync + 'static,
+{
+    let outcome = match ShortUrl::try_from(code) {
+        Ok(short_url) => {
+            let request = NewReport {
+                reason: body.reason,
+                details: body.details,
+                reporter_ip: client_ip(&state.config, &headers, connect_info),
+            };
+
+            state.link_service.report(short_url, request).await
+        }
+        Err(_) => Err(LinkError::InvalidFormat),
+    };
+
+    match outcome {
+        Ok(ReportOutcome::Recorded) => (
+            StatusCode::CREATED,
+            Json(serde_json::json!({ "status": "recorded" })),
+        )
+            .into_response(),
+        Ok(ReportOutcome::Duplicate) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "status": "duplicate" })),
+        )
+            .into_response(),
+        Err(e) => ApiError(e).into_response(),
+    }
+}
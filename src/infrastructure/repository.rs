@@ -1,14 +1,23 @@
 use chrono::{DateTime, Utc};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 use sqlx::types::time::OffsetDateTime;
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool};
+use std::str::FromStr;
+use std::time::Duration;
+use uuid::Uuid;
 
 use async_trait::async_trait;
 
 use crate::domain::{
+    click::{CampaignStats, Click, ClickStats, DailyClicks, VariantClicks},
     errors::LinkError,
-    link::{Link, LinkId, LinkKey, ShortUrl},
-    ports::{LinkPersistence, LinkQuery},
+    link::{
+        ActiveFrom, ExpiresAt, Link, LinkId, LinkKey, LinkStatus, RedirectKind, ShortUrl, Tag, Utm,
+    },
+    ports::{BatchMode, LinkPersistence, LinkQuery},
+    report::{Report, ReportReason, ReportState},
 };
+use crate::infrastructure::rules::{decode_rules, decode_variants, encode_rules, encode_variants};
 
 #[derive(Clone, Debug)]
 pub struct PgPoolRepository {
@@ -19,6 +28,25 @@ impl PgPoolRepository {
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
     }
+
+    // `timeout` bounds both each statement, enforced by Postgres itself, and
+    // the wait for a free connection, so a struggling database turns into
+    // quick `Unavailable` errors instead of a pile of stuck requests.
+    pub async fn connect(database_url: &str, timeout: Duration) -> Result<Self, sqlx::Error> {
+        let options = PgConnectOptions::from_str(database_url)?
+            .options([("statement_timeout", timeout.as_millis().to_string())]);
+
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(timeout)
+            .connect_with(options)
+            .await?;
+
+        Ok(Self::new(pool))
+    }
+
+    pub async fn migrate(&self) -> Result<(), sqlx::migrate::MigrateError> {
+        sqlx::migrate!().run(&self.pool).await
+    }
 }
 
 fn to_offset_dt(dt: DateTime<Utc>) -> Result<OffsetDateTime, LinkError> {
@@ -37,71 +65,432 @@ fn to_chrono_dt(offset_dt: OffsetDateTime) -> Result<DateTime<Utc>, LinkError> {
     Ok(chrono_dt)
 }
 
+// Wildcards typed by the caller are matched literally.
+fn like_pattern(term: &str) -> String {
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    format!("%{escaped}%")
+}
+
+// SQLSTATE for a statement cancelled by `statement_timeout`.
+const QUERY_CANCELED: &str = "57014";
+
+fn to_persistence_error(error: sqlx::Error) -> LinkError {
+    match &error {
+        sqlx::Error::Database(db_error)
+            if db_error.constraint() == Some("links_short_code_key") =>
+        {
+            LinkError::AliasTaken
+        }
+        sqlx::Error::Database(db_error) if db_error.code().as_deref() == Some(QUERY_CANCELED) => {
+            tracing::warn!("database statement timed out");
+            LinkError::Unavailable
+        }
+        sqlx::Error::PoolTimedOut => {
+            tracing::warn!("timed out waiting for a database connection");
+            LinkError::Unavailable
+        }
+        _ => LinkError::PersistenceError(error.to_string()),
+    }
+}
+
+struct LinkRow {
+    id: uuid::Uuid,
+    delete_key: String,
+    short_code: String,
+    long_url: String,
+    created_at: OffsetDateTime,
+    expires_at: Option<OffsetDateTime>,
+    tags: Vec<String>,
+    status: String,
+    always_preview: bool,
+    owner: Option<String>,
+    redirect_status: Option<i16>,
+    passthrough: bool,
+    utm_source: Option<String>,
+    utm_medium: Option<String>,
+    utm_campaign: Option<String>,
+    utm_term: Option<String>,
+    utm_content: Option<String>,
+    routing_rules: serde_json::Value,
+    variants: serde_json::Value,
+    active_from: Option<OffsetDateTime>,
+    status_reason: Option<String>,
+}
+
+impl TryFrom<LinkRow> for Link {
+    type Error = LinkError;
+
+    fn try_from(row: LinkRow) -> Result<Self, Self::Error> {
+        let created_at_utc = to_chrono_dt(row.created_at)?;
+
+        let expires_at = row
+            .expires_at
+            .map(to_chrono_dt)
+            .transpose()?
+            .map(ExpiresAt::from);
+
+        let active_from = row
+            .active_from
+            .map(to_chrono_dt)
+            .transpose()?
+            .map(ActiveFrom::from);
+
+        let tags = row
+            .tags
+            .into_iter()
+            .map(Tag::try_from)
+            .collect::<Result<Vec<Tag>, LinkError>>()?;
+
+        let redirect_kind = row
+            .redirect_status
+            .map(|status| RedirectKind::try_from(status as u16))
+            .transpose()?;
+
+        let link = Link::new(
+            row.id,
+            row.delete_key,
+            row.short_code,
+            row.long_url,
+            created_at_utc,
+        )
+        .map_err(|_| LinkError::LinkCreationError)?
+        .with_active_from(active_from)
+        .with_expires_at(expires_at)
+        .with_tags(tags)
+        .with_status(LinkStatus::try_from(row.status.as_str())?)
+        .with_status_reason(row.status_reason)
+        .with_always_preview(row.always_preview)
+        .with_owner(row.owner)
+        .with_redirect_kind(redirect_kind)
+        .with_passthrough(row.passthrough)
+        .with_utm(Utm {
+            source: row.utm_source,
+            medium: row.utm_medium,
+            campaign: row.utm_campaign,
+            term: row.utm_term,
+            content: row.utm_content,
+        })
+        .with_routing_rules(decode_rules(row.routing_rules)?)
+        .with_variants(decode_variants(row.variants)?);
+
+        Ok(link)
+    }
+}
+
+struct ReportRow {
+    id: Uuid,
+    link_id: Uuid,
+    reason: String,
+    details: Option<String>,
+    reporter_hash: String,
+    created_at: OffsetDateTime,
+    state: String,
+}
+
+impl TryFrom<ReportRow> for Report {
+    type Error = LinkError;
+
+    fn try_from(row: ReportRow) -> Result<Self, Self::Error> {
+        Ok(Report::restore(
+            row.id,
+            LinkId::from(row.link_id),
+            ReportReason::try_from(row.reason.as_str())?,
+            row.details,
+            row.reporter_hash,
+            to_chrono_dt(row.created_at)?,
+            ReportState::try_from(row.state.as_str())?,
+        ))
+    }
+}
+
+async fn insert_link<'e, E>(executor: E, link: Link) -> Result<LinkId, LinkError>
+where
+    E: PgExecutor<'e>,
+{
+    let id = link.id().clone().into_inner();
+    let delete_key = link.delete_hash_code().clone().into_inner();
+    let short_code = link.short_url().clone().into_inner();
+    let long_url = link.user_url().clone().into_inner();
+    let expires_at = link
+        .expires_at()
+        .cloned()
+        .map(|value| to_offset_dt(value.into_inner()))
+        .transpose()?;
+    let tags: Vec<String> = link.tags().iter().cloned().map(Tag::into_inner).collect();
+    let status = link.status().as_str();
+    let always_preview = link.always_preview();
+    let owner = link.owner().map(str::to_string);
+    let redirect_status = link.redirect_kind().map(|kind| kind.status_code() as i16);
+    let passthrough = link.passthrough();
+    let utm = link.utm().clone();
+    let routing_rules = encode_rules(link.routing_rules());
+    let variants = encode_variants(link.variants());
+    let active_from = link
+        .active_from()
+        .cloned()
+        .map(|value| to_offset_dt(value.into_inner()))
+        .transpose()?;
+    let status_reason = link.status_reason().map(str::to_string);
+    let created_at = to_offset_dt(link.created_at().into_inner())?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO links (
+            id, delete_key, short_code, long_url, created_at, expires_at, tags, status,
+            always_preview, owner, redirect_status, passthrough, utm_source, utm_medium,
+                utm_campaign, utm_term, utm_content, routing_rules, variants, active_from,
+                status_reason
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13,
+            $14, $15, $16, $17, $18, $19, $20, $21)
+        "#,
+        id,
+        delete_key,
+        short_code,
+        long_url,
+        created_at,
+        expires_at,
+        &tags,
+        status,
+        always_preview,
+        owner,
+        redirect_status,
+        passthrough,
+        utm.source,
+        utm.medium,
+        utm.campaign,
+        utm.term,
+        utm.content,
+        routing_rules,
+        variants,
+        active_from,
+        status_reason
+    )
+    .execute(executor)
+    .await
+    .map_err(to_persistence_error)?;
+
+    Ok(LinkId::from(id))
+}
+
+async fn insert_clicks<'e, E>(executor: E, clicks: Vec<Click>) -> Result<(), LinkError>
+where
+    E: PgExecutor<'e>,
+{
+    let mut link_ids = Vec::with_capacity(clicks.len());
+    let mut clicked_at = Vec::with_capacity(clicks.len());
+    let mut variants = Vec::with_capacity(clicks.len());
+
+    for click in clicks {
+        link_ids.push(click.link_id().clone().into_inner());
+        clicked_at.push(to_offset_dt(click.clicked_at())?);
+        variants.push(click.variant().map(str::to_string));
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO clicks (link_id, clicked_at, variant)
+        SELECT * FROM UNNEST($1::uuid[], $2::timestamptz[], $3::text[])
+        "#,
+        &link_ids,
+        &clicked_at,
+        &variants as &[Option<String>]
+    )
+    .execute(executor)
+    .await
+    .map_err(to_persistence_error)?;
+
+    Ok(())
+}
+
 #[async_trait]
 impl LinkPersistence for PgPoolRepository {
     async fn save(&self, link: Link) -> Result<LinkId, LinkError> {
-        let id = link.id().clone().into_inner();
-        let delete_key = link.delete_hash_code().clone().into_inner();
-        let short_code = link.short_url().clone().into_inner();
-        let long_url = link.user_url().clone().into_inner();
-        let created_at = to_offset_dt(link.created_at().into_inner())?;
+        insert_link(&self.pool, link).await
+    }
 
-        sqlx::query!(
+    async fn save_batch(
+        &self,
+        links: Vec<Link>,
+        mode: BatchMode,
+    ) -> Result<Vec<Result<LinkId, LinkError>>, LinkError> {
+        let mut results = Vec::with_capacity(links.len());
+
+        match mode {
+            BatchMode::BestEffort => {
+                for link in links {
+                    results.push(insert_link(&self.pool, link).await);
+                }
+            }
+            BatchMode::Atomic => {
+                let mut tx = self.pool.begin().await.map_err(to_persistence_error)?;
+                let total = links.len();
+
+                for link in links {
+                    match insert_link(&mut *tx, link).await {
+                        Ok(id) => results.push(Ok(id)),
+                        Err(e) => {
+                            tx.rollback().await.map_err(to_persistence_error)?;
+
+                            let failed_at = results.len();
+                            return Ok((0..total)
+                                .map(|row| {
+                                    if row == failed_at {
+                                        Err(e.clone())
+                                    } else {
+                                        Err(LinkError::BatchAborted)
+                                    }
+                                })
+                                .collect());
+                        }
+                    }
+                }
+
+                tx.commit().await.map_err(to_persistence_error)?;
+            }
+        }
+
+        Ok(results)
+    }
+
+    async fn record_clicks(&self, clicks: Vec<Click>) -> Result<(), LinkError> {
+        insert_clicks(&self.pool, clicks).await
+    }
+
+    async fn save_with_clicks(&self, link: Link, clicks: Vec<Click>) -> Result<LinkId, LinkError> {
+        let mut tx = self.pool.begin().await.map_err(to_persistence_error)?;
+
+        let id = insert_link(&mut *tx, link).await?;
+
+        if !clicks.is_empty() {
+            insert_clicks(&mut *tx, clicks).await?;
+        }
+
+        tx.commit().await.map_err(to_persistence_error)?;
+
+        Ok(id)
+    }
+
+    async fn set_status(
+        &self,
+        id: LinkId,
+        status: LinkStatus,
+        reason: Option<String>,
+    ) -> Result<(), LinkError> {
+        let result = sqlx::query!(
             r#"
-            INSERT INTO links (id, delete_key, short_code, long_url, created_at)
-            VALUES ($1,$2, $3, $4, $5)
+            UPDATE links
+            SET status = $2, status_reason = $3
+            WHERE id = $1
             "#,
-            id,
-            delete_key,
-            short_code,
-            long_url,
-            created_at
+            id.into_inner(),
+            status.as_str(),
+            reason
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(to_persistence_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(LinkError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn purge_expired(&self, now: DateTime<Utc>) -> Result<u64, LinkError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM links
+            WHERE expires_at <= $1
+            "#,
+            to_offset_dt(now)?
         )
         .execute(&self.pool)
         .await
-        .map_err(|e| LinkError::PersistenceError(e.to_string()))?;
+        .map_err(to_persistence_error)?;
 
-        Ok(LinkId::from(id))
+        Ok(result.rows_affected())
     }
 
     async fn delete_by_id(&self, id: LinkId) -> Result<Option<Link>, LinkError> {
-        let result = sqlx::query!(
+        sqlx::query_as!(
+            LinkRow,
             r#"
             DELETE FROM links
             WHERE id = $1
-            RETURNING id, delete_key, short_code, long_url, created_at
+            RETURNING id, delete_key, short_code, long_url, created_at, expires_at, tags, status,
+                always_preview, owner, redirect_status, passthrough, utm_source, utm_medium,
+                utm_campaign, utm_term, utm_content, routing_rules, variants, active_from,
+                status_reason
             "#,
             id.into_inner()
         )
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| LinkError::PersistenceError(e.to_string()))?;
-
-        match result {
-            Some(record) => {
-                let created_at_utc = to_chrono_dt(record.created_at)?;
-
-                let link = Link::new(
-                    record.id,
-                    record.delete_key,
-                    record.short_code,
-                    record.long_url,
-                    created_at_utc,
-                )
-                .map_err(|_| LinkError::LinkCreationError)?;
-                Ok(Some(link))
-            }
-            None => Ok(None),
+        .map_err(to_persistence_error)?
+        .map(Link::try_from)
+        .transpose()
+    }
+
+    async fn save_report(&self, report: Report) -> Result<bool, LinkError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO reports (id, link_id, reason, details, reporter_hash, created_at, state)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (link_id, reporter_hash) DO NOTHING
+            "#,
+            report.id(),
+            report.link_id().clone().into_inner(),
+            report.reason().as_str(),
+            report.details(),
+            report.reporter_hash(),
+            to_offset_dt(report.created_at())?,
+            report.state().as_str()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(to_persistence_error)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn set_report_state(&self, id: Uuid, state: ReportState) -> Result<(), LinkError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE reports
+            SET state = $2
+            WHERE id = $1
+            "#,
+            id,
+            state.as_str()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(to_persistence_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(LinkError::NotFound);
         }
+
+        Ok(())
     }
 }
 
 #[async_trait]
 impl LinkQuery for PgPoolRepository {
     async fn find_by_id(&self, id: LinkId) -> Result<Link, LinkError> {
-        sqlx::query!(
+        sqlx::query_as!(
+            LinkRow,
             r#"
-            SELECT id, delete_key, short_code, long_url, created_at
+            SELECT id, delete_key, short_code, long_url, created_at, expires_at, tags, status,
+                always_preview, owner, redirect_status, passthrough, utm_source, utm_medium,
+                utm_campaign, utm_term, utm_content, routing_rules, variants, active_from,
+                status_reason
             FROM links
             WHERE id = $1
             "#,
@@ -109,20 +498,9 @@ impl LinkQuery for PgPoolRepository {
         )
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| LinkError::PersistenceError(e.to_string()))?
-        .ok_or(LinkError::LinkIdNotFound)
-        .and_then(|row| {
-            let created_at_utc = to_chrono_dt(row.created_at)?;
-
-            Link::new(
-                row.id,
-                row.delete_key,
-                row.short_code,
-                row.long_url,
-                created_at_utc,
-            )
-            .map_err(|_| LinkError::LinkCreationError)
-        })
+        .map_err(to_persistence_error)?
+        .ok_or(LinkError::NotFound)
+        .and_then(Link::try_from)
     }
 
     async fn find_delete_key(&self, id: LinkId) -> Result<LinkKey, LinkError> {
@@ -136,15 +514,19 @@ impl LinkQuery for PgPoolRepository {
         )
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| LinkError::PersistenceError(e.to_string()))?
-        .ok_or(LinkError::LinkIdNotFound)
+        .map_err(to_persistence_error)?
+        .ok_or(LinkError::NotFound)
         .map(|row| LinkKey::new(row.delete_key))
     }
 
     async fn find_by_short_code(&self, code: ShortUrl) -> Result<Link, LinkError> {
-        sqlx::query!(
+        sqlx::query_as!(
+            LinkRow,
             r#"
-            SELECT id,delete_key,short_code,long_url,created_at
+            SELECT id, delete_key, short_code, long_url, created_at, expires_at, tags, status,
+                always_preview, owner, redirect_status, passthrough, utm_source, utm_medium,
+                utm_campaign, utm_term, utm_content, routing_rules, variants, active_from,
+                status_reason
             FROM links
             WHERE short_code = $1
             "#,
@@ -152,19 +534,284 @@ impl LinkQuery for PgPoolRepository {
         )
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| LinkError::PersistenceError(e.to_string()))?
-        .ok_or(LinkError::LinkIdNotFound)
-        .and_then(|row| {
-            let created_at_utc = to_chrono_dt(row.created_at)?;
-
-            Link::new(
-                row.id,
-                row.delete_key,
-                row.short_code,
-                row.long_url,
-                created_at_utc,
+        .map_err(to_persistence_error)?
+        .ok_or(LinkError::NotFound)
+        .and_then(Link::try_from)
+    }
+
+    async fn list(
+        &self,
+        offset: i64,
+        limit: i64,
+        status: Option<LinkStatus>,
+    ) -> Result<Vec<Link>, LinkError> {
+        sqlx::query_as!(
+            LinkRow,
+            r#"
+            SELECT id, delete_key, short_code, long_url, created_at, expires_at, tags, status,
+                always_preview, owner, redirect_status, passthrough, utm_source, utm_medium,
+                utm_campaign, utm_term, utm_content, routing_rules, variants, active_from,
+                status_reason
+            FROM links
+            WHERE $3::text IS NULL OR status = $3
+            ORDER BY created_at, id
+            OFFSET $1
+            LIMIT $2
+            "#,
+            offset,
+            limit,
+            status.map(|status| status.as_str())
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(to_persistence_error)?
+        .into_iter()
+        .map(Link::try_from)
+        .collect()
+    }
+
+    async fn search(
+        &self,
+        term: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Link>, LinkError> {
+        sqlx::query_as!(
+            LinkRow,
+            r#"
+            SELECT id, delete_key, short_code, long_url, created_at, expires_at, tags, status,
+                always_preview, owner, redirect_status, passthrough, utm_source, utm_medium,
+                utm_campaign, utm_term, utm_content, routing_rules, variants, active_from,
+                status_reason
+            FROM links
+            WHERE $1::text IS NULL OR short_code ILIKE $1 OR long_url ILIKE $1
+            ORDER BY created_at DESC, id DESC
+            OFFSET $2
+            LIMIT $3
+            "#,
+            term.map(like_pattern),
+            offset,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(to_persistence_error)?
+        .into_iter()
+        .map(Link::try_from)
+        .collect()
+    }
+
+    async fn find_by_owner_and_url(
+        &self,
+        owner: &str,
+        long_url: &str,
+    ) -> Result<Option<Link>, LinkError> {
+        sqlx::query_as!(
+            LinkRow,
+            r#"
+            SELECT id, delete_key, short_code, long_url, created_at, expires_at, tags, status,
+                always_preview, owner, redirect_status, passthrough, utm_source, utm_medium,
+                utm_campaign, utm_term, utm_content, routing_rules, variants, active_from,
+                status_reason
+            FROM links
+            WHERE owner = $1 AND long_url = $2 AND status = $3
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+            owner,
+            long_url,
+            LinkStatus::Active.as_str()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(to_persistence_error)?
+        .map(Link::try_from)
+        .transpose()
+    }
+
+    async fn find_clicks(&self, id: LinkId) -> Result<Vec<Click>, LinkError> {
+        let link_id = id.clone();
+
+        sqlx::query!(
+            r#"
+            SELECT clicked_at, variant
+            FROM clicks
+            WHERE link_id = $1
+            ORDER BY clicked_at
+            "#,
+            id.into_inner()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(to_persistence_error)?
+        .into_iter()
+        .map(|row| {
+            Ok(
+                Click::new(link_id.clone(), to_chrono_dt(row.clicked_at)?)
+                    .with_variant(row.variant),
             )
-            .map_err(|_| LinkError::LinkCreationError)
         })
+        .collect()
+    }
+
+    async fn click_stats(&self, id: LinkId) -> Result<ClickStats, LinkError> {
+        let id = id.into_inner();
+
+        let row = sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "total!", MIN(clicked_at) AS first_clicked_at, MAX(clicked_at) AS last_clicked_at
+            FROM clicks
+            WHERE link_id = $1
+            "#,
+            id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(to_persistence_error)?;
+
+        let by_variant = sqlx::query!(
+            r#"
+            SELECT variant AS "variant!", COUNT(*) AS "clicks!"
+            FROM clicks
+            WHERE link_id = $1 AND variant IS NOT NULL
+            GROUP BY variant
+            ORDER BY variant
+            "#,
+            id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(to_persistence_error)?
+        .into_iter()
+        .map(|row| VariantClicks {
+            variant: row.variant,
+            clicks: row.clicks,
+        })
+        .collect();
+
+        Ok(ClickStats {
+            total: row.total,
+            first_clicked_at: row.first_clicked_at.map(to_chrono_dt).transpose()?,
+            last_clicked_at: row.last_clicked_at.map(to_chrono_dt).transpose()?,
+            by_variant,
+        })
+    }
+
+    async fn daily_clicks(
+        &self,
+        id: LinkId,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<DailyClicks>, LinkError> {
+        sqlx::query!(
+            r#"
+            SELECT date_trunc('day', clicked_at, 'UTC') AS "day!", COUNT(*) AS "clicks!"
+            FROM clicks
+            WHERE link_id = $1 AND clicked_at >= $2
+            GROUP BY 1
+            ORDER BY 1
+            "#,
+            id.into_inner(),
+            to_offset_dt(since)?
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(to_persistence_error)?
+        .into_iter()
+        .map(|row| {
+            Ok(DailyClicks {
+                day: to_chrono_dt(row.day)?.date_naive(),
+                clicks: row.clicks,
+            })
+        })
+        .collect()
+    }
+
+    async fn campaign_stats(&self) -> Result<Vec<CampaignStats>, LinkError> {
+        sqlx::query!(
+            r#"
+            SELECT links.utm_campaign AS "campaign!",
+                COUNT(DISTINCT links.id) AS "links!",
+                COUNT(clicks.link_id) AS "clicks!",
+                MAX(clicks.clicked_at) AS last_clicked_at
+            FROM links
+            LEFT JOIN clicks ON clicks.link_id = links.id
+            WHERE links.utm_campaign IS NOT NULL
+            GROUP BY links.utm_campaign
+            ORDER BY 3 DESC, 1
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(to_persistence_error)?
+        .into_iter()
+        .map(|row| {
+            Ok(CampaignStats {
+                campaign: row.campaign,
+                links: row.links,
+                clicks: row.clicks,
+                last_clicked_at: row.last_clicked_at.map(to_chrono_dt).transpose()?,
+            })
+        })
+        .collect()
+    }
+
+    async fn find_report(&self, id: Uuid) -> Result<Report, LinkError> {
+        sqlx::query_as!(
+            ReportRow,
+            r#"
+            SELECT id, link_id, reason, details, reporter_hash, created_at, state
+            FROM reports
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(to_persistence_error)?
+        .ok_or(LinkError::NotFound)
+        .and_then(Report::try_from)
+    }
+
+    async fn list_reports(
+        &self,
+        state: Option<ReportState>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Report>, LinkError> {
+        sqlx::query_as!(
+            ReportRow,
+            r#"
+            SELECT id, link_id, reason, details, reporter_hash, created_at, state
+            FROM reports
+            WHERE $1::text IS NULL OR state = $1
+            ORDER BY created_at, id
+            OFFSET $2
+            LIMIT $3
+            "#,
+            state.map(|state| state.as_str()),
+            offset,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(to_persistence_error)?
+        .into_iter()
+        .map(Report::try_from)
+        .collect()
+    }
+
+    async fn count_open_reports(&self, link_id: LinkId) -> Result<i64, LinkError> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM reports
+            WHERE link_id = $1 AND state = $2
+            "#,
+            link_id.into_inner(),
+            ReportState::Open.as_str()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(to_persistence_error)
     }
 }
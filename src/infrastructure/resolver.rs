@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use std::io;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::domain::ports::HostResolver;
+
+#[derive(Debug, Clone)]
+pub struct TokioResolver {
+    timeout: Duration,
+}
+
+impl TokioResolver {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+#[async_trait]
+impl HostResolver for TokioResolver {
+    async fn resolve(&self, host: &str) -> io::Result<Vec<IpAddr>> {
+        let lookup = tokio::net::lookup_host((host, 0));
+
+        let addrs = tokio::time::timeout(self.timeout, lookup)
+            .await
+            .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??;
+
+        Ok(addrs.map(|addr| addr.ip()).collect())
+    }
+}
@@ -0,0 +1,97 @@
+use axum::{
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{get, post},
+    Router,
+};
+use tower_http::{
+    limit::RequestBodyLimitLayer, services::ServeDir, validate_request::ValidateRequestHeaderLayer,
+};
+
+use crate::domain::ports::{LinkPersistence, LinkQuery};
+use crate::infrastructure::admin::{
+    campaign_report, change_link_status, export_links, import_links, reload_link_lists,
+    report_queue, review_report,
+};
+use crate::infrastructure::caching::compression_layer;
+use crate::infrastructure::dashboard::{
+    abuse_queue, dashboard, link_detail, manage_link, review_report_form,
+};
+use crate::infrastructure::handlers::{
+    bulk_create_links, create_link, create_link_api, delete_link, home, link_metadata, link_stats,
+    redirect_link, AppState,
+};
+use crate::infrastructure::limits::{admin_request_timeout, request_timeout};
+use crate::infrastructure::qr::qr_code;
+use crate::infrastructure::reports::{report_form, report_link_api, submit_report_form};
+use crate::infrastructure::security::{cors_layer, security_headers};
+
+pub fn router<P, Q>(state: AppState<P, Q>) -> Router
+where
+    P: LinkPersistence + Send + Sync + Clone + 'static,
+    Q: LinkQuery + Send + Sync + Clone + 'static,
+{
+    let mut api = Router::new()
+        .route("/api/links", post(create_link_api))
+        .route("/api/links/bulk", post(bulk_create_links))
+        .route("/api/links/:code", get(link_metadata))
+        .route("/api/links/:code/stats", get(link_stats))
+        .route("/api/links/:code/report", post(report_link_api));
+
+    if let Some(cors) = cors_layer(&state.config) {
+        api = api.layer(cors);
+    }
+
+    let mut app = Router::new()
+        .route("/", get(home))
+        .nest_service("/static", ServeDir::new(&state.config.static_dir))
+        .route("/links", post(create_link))
+        .route("/l/:code", get(redirect_link).post(redirect_link))
+        .route("/l/:code/qr", get(qr_code))
+        .route("/l/:code/report", get(report_form).post(submit_report_form))
+        .route("/l/:code/*rest", get(redirect_link).post(redirect_link))
+        .route("/links/:id/delete", post(delete_link))
+        .merge(api)
+        .layer(RequestBodyLimitLayer::new(state.config.max_body_bytes))
+        .layer(middleware::from_fn_with_state(
+            state.config.clone(),
+            request_timeout,
+        ));
+
+    // OWASP A01 Broken Access Control
+    if let Some(password) = &state.config.admin_password {
+        let admin = Router::new()
+            .route("/", get(dashboard))
+            .route("/links/:id", get(link_detail))
+            .route("/links/:id/manage", post(manage_link))
+            .route("/abuse", get(abuse_queue))
+            .route("/abuse/:id", post(review_report_form))
+            .route("/export", get(export_links))
+            .route("/import", post(import_links))
+            .route("/campaigns", get(campaign_report))
+            .route("/lists/reload", post(reload_link_lists))
+            .route("/links/:id/status", post(change_link_status))
+            .route("/reports", get(report_queue))
+            .route("/reports/:id/:decision", post(review_report))
+            .layer(RequestBodyLimitLayer::new(state.config.max_import_bytes))
+            .layer(ValidateRequestHeaderLayer::basic(
+                &state.config.admin_user,
+                password,
+            ))
+            .layer(middleware::from_fn_with_state(
+                state.config.clone(),
+                admin_request_timeout,
+            ));
+
+        app = app.nest("/admin", admin);
+    }
+
+    // The limits above replace axum's built-in 2 MB extractor limit.
+    app.layer(DefaultBodyLimit::disable())
+        .layer(compression_layer())
+        .layer(middleware::map_response_with_state(
+            state.config.clone(),
+            security_headers,
+        ))
+        .with_state(state)
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+
+use crate::application::command::{NewRoutingRule, NewVariant};
+use crate::domain::{
+    errors::LinkError,
+    link::UserUrl,
+    routing::{DeviceKind, OsKind, RoutingRule, RuleConditions, Variant},
+};
+
+// Wire and storage shapes of routing rules and A/B variants: the JSON API,
+// exports and the `routing_rules`/`variants` JSONB columns all use them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuleRecord {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub device: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub os: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub language: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub country: Vec<String>,
+    pub url: String,
+}
+
+impl RuleRecord {
+    fn conditions(&self) -> Result<RuleConditions, LinkError> {
+        Ok(RuleConditions {
+            devices: self
+                .device
+                .iter()
+                .map(|device| DeviceKind::try_from(device.as_str()))
+                .collect::<Result<_, _>>()?,
+            os: self
+                .os
+                .iter()
+                .map(|os| OsKind::try_from(os.as_str()))
+                .collect::<Result<_, _>>()?,
+            languages: self.language.clone(),
+            countries: self.country.clone(),
+        })
+    }
+
+    // Untrusted input: the destination is validated by the service.
+    pub fn into_new_rule(self) -> Result<NewRoutingRule, LinkError> {
+        Ok(NewRoutingRule {
+            conditions: self.conditions()?,
+            url: self.url,
+        })
+    }
+
+    // Stored or exported rules were validated when the link was created.
+    pub fn into_rule(self) -> Result<RoutingRule, LinkError> {
+        let conditions = self.conditions()?.normalised()?;
+        let destination =
+            UserUrl::try_from(self.url).map_err(|e| LinkError::InvalidRule(e.to_string()))?;
+
+        Ok(RoutingRule::new(conditions, destination))
+    }
+}
+
+impl From<&RoutingRule> for RuleRecord {
+    fn from(rule: &RoutingRule) -> Self {
+        let conditions = rule.conditions();
+
+        Self {
+            device: conditions
+                .devices
+                .iter()
+                .map(|device| device.as_str().to_string())
+                .collect(),
+            os: conditions
+                .os
+                .iter()
+                .map(|os| os.as_str().to_string())
+                .collect(),
+            language: conditions.languages.clone(),
+            country: conditions.countries.clone(),
+            url: rule.destination().as_str().to_string(),
+        }
+    }
+}
+
+pub fn encode_rules(rules: &[RoutingRule]) -> serde_json::Value {
+    serde_json::Value::Array(
+        rules
+            .iter()
+            .map(RuleRecord::from)
+            .filter_map(|record| serde_json::to_value(record).ok())
+            .collect(),
+    )
+}
+
+pub fn decode_rules(value: serde_json::Value) -> Result<Vec<RoutingRule>, LinkError> {
+    serde_json::from_value::<Vec<RuleRecord>>(value)
+        .map_err(|e| LinkError::InvalidRule(e.to_string()))?
+        .into_iter()
+        .map(RuleRecord::into_rule)
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VariantRecord {
+    pub name: String,
+    pub url: String,
+    pub weight: u32,
+}
+
+impl VariantRecord {
+    pub fn into_new_variant(self) -> NewVariant {
+        NewVariant {
+            name: self.name,
+            url: self.url,
+            weight: self.weight,
+        }
+    }
+
+    pub fn into_variant(self) -> Result<Variant, LinkError> {
+        let destination =
+            UserUrl::try_from(self.url).map_err(|e| LinkError::InvalidVariant(e.to_string()))?;
+
+        Variant::new(&self.name, destination, self.weight)
+    }
+}
+
+impl From<&Variant> for VariantRecord {
+    fn from(variant: &Variant) -> Self {
+        Self {
+            name: variant.name().to_string(),
+            url: variant.destination().as_str().to_string(),
+            weight: variant.weight(),
+        }
+    }
+}
+
+pub fn encode_variants(variants: &[Variant]) -> serde_json::Value {
+    serde_json::Value::Array(
+        variants
+            .iter()
+            .map(VariantRecord::from)
+            .filter_map(|record| serde_json::to_value(record).ok())
+            .collect(),
+    )
+}
+
+pub fn decode_variants(value: serde_json::Value) -> Result<Vec<Variant>, LinkError> {
+    serde_json::from_value::<Vec<VariantRecord>>(value)
+        .map_err(|e| LinkError::InvalidVariant(e.to_string()))?
+        .into_iter()
+        .map(VariantRecord::into_variant)
+        .collect()
+}
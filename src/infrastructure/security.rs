@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method},
+    response::Response,
+};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::infrastructure::config::AppConfig;
+
+// Pages load only their own script, stylesheet and images, and no other
+// site may frame them.
+const CONTENT_SECURITY_POLICY: &str = "default-src 'self'; script-src 'self'; \
+     style-src 'self'; img-src 'self'; object-src 'none'; base-uri 'none'; \
+     form-action 'self'; frame-ancestors 'none'";
+
+// OWASP A05 Security Misconfiguration: added to every response, redirects
+// included. A header a handler already set is left alone.
+pub async fn security_headers(
+    State(config): State<Arc<AppConfig>>,
+    mut response: Response,
+) -> Response {
+    let headers = response.headers_mut();
+
+    set_default(
+        headers,
+        header::CONTENT_SECURITY_POLICY,
+        HeaderValue::from_static(CONTENT_SECURITY_POLICY),
+    );
+    set_default(
+        headers,
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    set_default(
+        headers,
+        header::REFERRER_POLICY,
+        HeaderValue::from_static("strict-origin-when-cross-origin"),
+    );
+    // `frame-ancestors` for browsers that predate it.
+    set_default(
+        headers,
+        header::X_FRAME_OPTIONS,
+        HeaderValue::from_static("DENY"),
+    );
+
+    if config.hsts_max_age > 0 {
+        let subdomains = if config.hsts_include_subdomains {
+            "; includeSubDomains"
+        } else {
+            ""
+        };
+
+        if let Ok(value) =
+            HeaderValue::try_from(format!("max-age={}{subdomains}", config.hsts_max_age))
+        {
+            set_default(headers, header::STRICT_TRANSPORT_SECURITY, value);
+        }
+    }
+
+    response
+}
+
+fn set_default(headers: &mut HeaderMap, name: HeaderName, value: HeaderValue) {
+    headers.entry(name).or_insert(value);
+}
+
+// Only the JSON API is exposed cross-origin. Callers authenticate with an
+// API key header, never cookies, so credentials stay disallowed.
+pub fn cors_layer(config: &AppConfig) -> Option<CorsLayer> {
+    if config.cors_allowed_origins.is_empty() {
+        return None;
+    }
+
+    let allow_origin = if config
+        .cors_allowed_origins
+        .iter()
+        .any(|origin| origin == "*")
+    {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            config
+                .cors_allowed_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::try_from(origin.as_str()).ok()),
+        )
+    };
+
+    Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST])
+            .allow_headers([
+                header::CONTENT_TYPE,
+                header::AUTHORIZATION,
+                HeaderName::from_static("x-api-key"),
+            ])
+            .max_age(config.cors_max_age),
+    )
+}
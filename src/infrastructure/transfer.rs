@@ -0,0 +1,504 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::application::transfer::{ImportConflict, ImportOutcome, LinkEntry};
+use crate::domain::{
+    click::Click,
+    link::{ActiveFrom, ExpiresAt, Link, LinkId, LinkStatus, RedirectKind, Tag, Utm},
+};
+use crate::infrastructure::errors::public_message;
+use crate::infrastructure::rules::{RuleRecord, VariantRecord};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferFormat {
+    #[default]
+    Jsonl,
+    Csv,
+}
+
+impl TransferFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            TransferFormat::Jsonl => "application/x-ndjson",
+            TransferFormat::Csv => "text/csv",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonRecord {
+    id: String,
+    short_code: String,
+    long_url: String,
+    created_at: String,
+    delete_key: String,
+    #[serde(default)]
+    active_from: Option<String>,
+    expires_at: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default = "default_status")]
+    status: String,
+    #[serde(default)]
+    status_reason: Option<String>,
+    #[serde(default)]
+    always_preview: bool,
+    #[serde(default)]
+    owner: Option<String>,
+    #[serde(default)]
+    redirect_status: Option<u16>,
+    #[serde(default)]
+    passthrough: bool,
+    #[serde(default)]
+    utm_source: Option<String>,
+    #[serde(default)]
+    utm_medium: Option<String>,
+    #[serde(default)]
+    utm_campaign: Option<String>,
+    #[serde(default)]
+    utm_term: Option<String>,
+    #[serde(default)]
+    utm_content: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    routing_rules: Vec<RuleRecord>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    variants: Vec<VariantRecord>,
+    // `<timestamp>` or `<timestamp>@<variant>` for clicks on a split link.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    clicks: Vec<String>,
+}
+
+// CSV has no arrays, so tags and click timestamps are `;`-separated cells.
+#[derive(Serialize, Deserialize)]
+struct CsvRecord {
+    id: String,
+    short_code: String,
+    long_url: String,
+    created_at: String,
+    delete_key: String,
+    #[serde(default)]
+    active_from: Option<String>,
+    expires_at: Option<String>,
+    tags: String,
+    #[serde(default = "default_status")]
+    status: String,
+    #[serde(default)]
+    status_reason: Option<String>,
+    #[serde(default)]
+    always_preview: bool,
+    #[serde(default)]
+    owner: Option<String>,
+    #[serde(default)]
+    redirect_status: Option<u16>,
+    #[serde(default)]
+    passthrough: bool,
+    #[serde(default)]
+    utm_source: Option<String>,
+    #[serde(default)]
+    utm_medium: Option<String>,
+    #[serde(default)]
+    utm_campaign: Option<String>,
+    #[serde(default)]
+    utm_term: Option<String>,
+    #[serde(default)]
+    utm_content: Option<String>,
+    // The rules as a JSON array, empty when there are none.
+    #[serde(default)]
+    routing_rules: String,
+    // Likewise a JSON array.
+    #[serde(default)]
+    variants: String,
+    clicks: String,
+}
+
+fn default_status() -> String {
+    LinkStatus::Active.as_str().to_string()
+}
+
+fn format_dt(value: DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn parse_dt(value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|_| format!("Invalid timestamp '{value}'"))
+}
+
+fn format_click(click: &Click) -> String {
+    match click.variant() {
+        Some(variant) => format!("{}@{variant}", format_dt(click.clicked_at())),
+        None => format_dt(click.clicked_at()),
+    }
+}
+
+fn parse_click(link_id: LinkId, value: &str) -> Result<Click, String> {
+    let (at, variant) = match value.split_once('@') {
+        Some((at, variant)) => (at, Some(variant.trim().to_string())),
+        None => (value, None),
+    };
+
+    Ok(Click::new(link_id, parse_dt(at)?).with_variant(variant))
+}
+
+fn split_cell(value: &str) -> Vec<String> {
+    value
+        .split(';')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn to_json_record(entry: &LinkEntry) -> JsonRecord {
+    let link = &entry.link;
+
+    JsonRecord {
+        id: link.id().clone().into_inner().to_string(),
+        short_code: link.short_url().as_str().to_string(),
+        long_url: link.user_url().as_str().to_string(),
+        created_at: format_dt(link.clone().created_at().into_inner()),
+        delete_key: link.delete_hash_code().value().to_string(),
+        active_from: link
+            .active_from()
+            .map(|value| format_dt(value.clone().into_inner())),
+        expires_at: link
+            .expires_at()
+            .map(|value| format_dt(value.clone().into_inner())),
+        tags: link
+            .tags()
+            .iter()
+            .map(|tag| tag.as_str().to_string())
+            .collect(),
+        status: link.status().as_str().to_string(),
+        status_reason: link.status_reason().map(str::to_string),
+        always_preview: link.always_preview(),
+        owner: link.owner().map(str::to_string),
+        redirect_status: link.redirect_kind().map(|kind| kind.status_code()),
+        passthrough: link.passthrough(),
+        utm_source: link.utm().source.clone(),
+        utm_medium: link.utm().medium.clone(),
+        utm_campaign: link.utm().campaign.clone(),
+        utm_term: link.utm().term.clone(),
+        utm_content: link.utm().content.clone(),
+        routing_rules: link.routing_rules().iter().map(RuleRecord::from).collect(),
+        variants: link.variants().iter().map(VariantRecord::from).collect(),
+        clicks: entry.clicks.iter().map(format_click).collect(),
+    }
+}
+
+fn from_json_record(record: JsonRecord) -> Result<LinkEntry, String> {
+    let id = Uuid::parse_str(record.id.trim()).map_err(|_| "Invalid id".to_string())?;
+
+    let expires_at = record
+        .expires_at
+        .as_deref()
+        .filter(|value| !value.trim().is_empty())
+        .map(parse_dt)
+        .transpose()?
+        .map(ExpiresAt::from);
+
+    let active_from = record
+        .active_from
+        .as_deref()
+        .filter(|value| !value.trim().is_empty())
+        .map(parse_dt)
+        .transpose()?
+        .map(ActiveFrom::from);
+
+    let tags = record
+        .tags
+        .into_iter()
+        .map(Tag::try_from)
+        .collect::<Result<Vec<Tag>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let status = LinkStatus::try_from(record.status.as_str()).map_err(|e| e.to_string())?;
+
+    let redirect_kind = record
+        .redirect_status
+        .map(RedirectKind::try_from)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    let utm = Utm {
+        source: record.utm_source,
+        medium: record.utm_medium,
+        campaign: record.utm_campaign,
+        term: record.utm_term,
+        content: record.utm_content,
+    }
+    .normalised()
+    .map_err(|e| e.to_string())?;
+
+    let routing_rules = record
+        .routing_rules
+        .into_iter()
+        .map(RuleRecord::into_rule)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let variants = record
+        .variants
+        .into_iter()
+        .map(VariantRecord::into_variant)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let clicks = record
+        .clicks
+        .iter()
+        .map(|value| parse_click(LinkId::from(id), value))
+        .collect::<Result<Vec<Click>, String>>()?;
+
+    let link = Link::new(
+        id,
+        record.delete_key,
+        record.short_code,
+        record.long_url,
+        parse_dt(&record.created_at)?,
+    )?
+    .with_active_from(active_from)
+    .with_expires_at(expires_at)
+    .with_tags(tags)
+    .with_status(status)
+    .with_status_reason(
+        record
+            .status_reason
+            .filter(|reason| !reason.trim().is_empty()),
+    )
+    .with_always_preview(record.always_preview)
+    .with_owner(record.owner.filter(|owner| !owner.trim().is_empty()))
+    .with_redirect_kind(redirect_kind)
+    .with_passthrough(record.passthrough)
+    .with_utm(utm)
+    .with_routing_rules(routing_rules)
+    .with_variants(variants);
+
+    Ok(LinkEntry { link, clicks })
+}
+
+pub fn encode_entries(
+    entries: &[LinkEntry],
+    format: TransferFormat,
+    with_header: bool,
+) -> Result<Vec<u8>, String> {
+    match format {
+        TransferFormat::Jsonl => {
+            let mut out = Vec::new();
+
+            for entry in entries {
+                serde_json::to_writer(&mut out, &to_json_record(entry))
+                    .map_err(|e| e.to_string())?;
+                out.push(b'\n');
+            }
+
+            Ok(out)
+        }
+        TransferFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(with_header)
+                .from_writer(Vec::new());
+
+            for entry in entries {
+                let record = to_json_record(entry);
+
+                writer
+                    .serialize(CsvRecord {
+                        id: record.id,
+                        short_code: record.short_code,
+                        long_url: record.long_url,
+                        created_at: record.created_at,
+                        delete_key: record.delete_key,
+                        active_from: record.active_from,
+                        expires_at: record.expires_at,
+                        tags: record.tags.join(";"),
+                        status: record.status,
+                        status_reason: record.status_reason,
+                        always_preview: record.always_preview,
+                        owner: record.owner,
+                        redirect_status: record.redirect_status,
+                        passthrough: record.passthrough,
+                        utm_source: record.utm_source,
+                        utm_medium: record.utm_medium,
+                        utm_campaign: record.utm_campaign,
+                        utm_term: record.utm_term,
+                        utm_content: record.utm_content,
+                        routing_rules: if record.routing_rules.is_empty() {
+                            String::new()
+                        } else {
+                            serde_json::to_string(&record.routing_rules)
+                                .map_err(|e| e.to_string())?
+                        },
+                        variants: if record.variants.is_empty() {
+                            String::new()
+                        } else {
+                            serde_json::to_string(&record.variants).map_err(|e| e.to_string())?
+                        },
+                        clicks: record.clicks.join(";"),
+                    })
+                    .map_err(|e| e.to_string())?;
+            }
+
+            // An empty page still owes the reader a header row.
+            if entries.is_empty() && with_header {
+                writer
+                    .write_record([
+                        "id",
+                        "short_code",
+                        "long_url",
+                        "created_at",
+                        "delete_key",
+                        "active_from",
+                        "expires_at",
+                        "tags",
+                        "status",
+                        "status_reason",
+                        "always_preview",
+                        "owner",
+                        "redirect_status",
+                        "passthrough",
+                        "utm_source",
+                        "utm_medium",
+                        "utm_campaign",
+                        "utm_term",
+                        "utm_content",
+                        "routing_rules",
+                        "variants",
+                        "clicks",
+                    ])
+                    .map_err(|e| e.to_string())?;
+            }
+
+            writer.into_inner().map_err(|e| e.to_string())
+        }
+    }
+}
+
+// Each row is decoded independently so one bad line is reported instead of
+// failing the whole import.
+pub fn decode_entries(body: &[u8], format: TransferFormat) -> Vec<Result<LinkEntry, String>> {
+    match format {
+        TransferFormat::Jsonl => String::from_utf8_lossy(body)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str::<JsonRecord>(line)
+                    .map_err(|e| format!("Invalid JSON: {e}"))
+                    .and_then(from_json_record)
+            })
+            .collect(),
+        TransferFormat::Csv => csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(body)
+            .deserialize::<CsvRecord>()
+            .map(|row| {
+                let row = row.map_err(|e| format!("Invalid CSV: {e}"))?;
+
+                from_json_record(JsonRecord {
+                    id: row.id,
+                    short_code: row.short_code,
+                    long_url: row.long_url,
+                    created_at: row.created_at,
+                    delete_key: row.delete_key,
+                    active_from: row.active_from,
+                    expires_at: row.expires_at,
+                    tags: split_cell(&row.tags),
+                    status: row.status,
+                    status_reason: row.status_reason,
+                    always_preview: row.always_preview,
+                    owner: row.owner,
+                    redirect_status: row.redirect_status,
+                    passthrough: row.passthrough,
+                    utm_source: row.utm_source,
+                    utm_medium: row.utm_medium,
+                    utm_campaign: row.utm_campaign,
+                    utm_term: row.utm_term,
+                    utm_content: row.utm_content,
+                    routing_rules: if row.routing_rules.trim().is_empty() {
+                        Vec::new()
+                    } else {
+                        serde_json::from_str(&row.routing_rules)
+                            .map_err(|e| format!("Invalid routing rules: {e}"))?
+                    },
+                    variants: if row.variants.trim().is_empty() {
+                        Vec::new()
+                    } else {
+                        serde_json::from_str(&row.variants)
+                            .map_err(|e| format!("Invalid variants: {e}"))?
+                    },
+                    clicks: split_cell(&row.clicks),
+                })
+            })
+            .collect(),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportRowReport {
+    pub row: usize,
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub imported: usize,
+    pub conflicts: usize,
+    pub failed: usize,
+    pub rows: Vec<ImportRowReport>,
+}
+
+impl ImportReport {
+    // `decoded` carries one slot per input row; rows that failed to decode
+    // hold their error and have no matching service outcome.
+    pub fn new(
+        dry_run: bool,
+        decoded: Vec<Result<(), String>>,
+        outcomes: Vec<ImportOutcome>,
+    ) -> Self {
+        let mut outcomes = outcomes.into_iter();
+
+        let rows: Vec<ImportRowReport> = decoded
+            .into_iter()
+            .enumerate()
+            .map(|(index, decoded)| {
+                let (status, detail) = match decoded {
+                    Err(e) => ("failed", Some(e)),
+                    Ok(()) => match outcomes.next() {
+                        Some(ImportOutcome::Imported) => ("imported", None),
+                        Some(ImportOutcome::WouldImport) => ("would_import", None),
+                        Some(ImportOutcome::Conflict(ImportConflict::IdExists)) => {
+                            ("conflict", Some("id already exists".to_string()))
+                        }
+                        Some(ImportOutcome::Conflict(ImportConflict::ShortCodeExists)) => {
+                            ("conflict", Some("short code already exists".to_string()))
+                        }
+                        Some(ImportOutcome::Failed(e)) => ("failed", Some(public_message(&e))),
+                        None => ("failed", Some("missing outcome".to_string())),
+                    },
+                };
+
+                ImportRowReport {
+                    row: index + 1,
+                    status,
+                    detail,
+                }
+            })
+            .collect();
+
+        let count = |status: &str| rows.iter().filter(|row| row.status == status).count();
+
+        Self {
+            dry_run,
+            imported: count("imported") + count("would_import"),
+            conflicts: count("conflict"),
+            failed: count("failed"),
+            rows,
+        }
+    }
+}
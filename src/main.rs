@@ -1,58 +1,88 @@
-use axum::{
-    routing::{get, post},
-    Router,
-};
-use dotenvy;
 use rustlink::application::service::LinkService;
 use rustlink::application::usecase::{LinkPersistenceService, LinkQueryService};
-use rustlink::infrastructure::handlers::{create_link, delete_link, redirect_link, AppState};
+use rustlink::domain::ports::GeoLocator;
+use rustlink::infrastructure::config::AppConfig;
+use rustlink::infrastructure::geoip::MaxMindLocator;
+use rustlink::infrastructure::handlers::AppState;
+use rustlink::infrastructure::lists::{reload_lists, reload_on_sighup};
+use rustlink::infrastructure::qr::QrCache;
 use rustlink::infrastructure::repository::PgPoolRepository;
-use sqlx::PgPool;
+use rustlink::infrastructure::resolver::TokioResolver;
+use rustlink::infrastructure::routes::router;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::net::TcpListener;
+use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenvy::dotenv().ok();
 
-    let database_url = std::env::var("DATABASE_URL").expect("FATAL: DATABASE_URL NOT SET");
+    // OWASP A09 Security Logging and Monitoring Failures
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
 
-    let pool = PgPool::connect(&database_url)
-        .await
-        .expect("FATAL: FAILED TO CONNECT TO DATABASE");
+    let config = AppConfig::from_env();
 
-    sqlx::migrate!()
-        .run(&pool)
+    let repo = PgPoolRepository::connect(&config.database_url, config.db_timeout)
         .await
-        .expect("FATAL: FAILED TO RUN MIGRATION");
+        .expect("FATAL: FAILED TO CONNECT TO DATABASE");
 
     type P = PgPoolRepository;
     type Q = PgPoolRepository;
     type RealService = LinkService<P, Q>;
     type RealState = AppState<P, Q>;
 
-    let repo = PgPoolRepository::new(pool);
+    repo.migrate()
+        .await
+        .expect("FATAL: FAILED TO RUN MIGRATION");
 
     let link_service_persistence = LinkPersistenceService::new(repo.clone());
     let link_service_query = LinkQueryService::new(repo);
 
-    let link_service = RealService::new(link_service_persistence, link_service_query).await;
+    let resolver = Arc::new(TokioResolver::new(config.dns_timeout));
+
+    let geo_locator = config
+        .geoip_db_path
+        .as_deref()
+        .map(MaxMindLocator::open)
+        .transpose()
+        .expect("FATAL: FAILED TO OPEN GEOIP DATABASE")
+        .map(|locator| Arc::new(locator) as Arc<dyn GeoLocator>);
+
+    let link_service = RealService::new(link_service_persistence, link_service_query, resolver)
+        .await
+        .with_strip_tracking_params(config.strip_tracking_params)
+        .with_query_conflict(config.query_conflict)
+        .with_geo_locator(geo_locator)
+        .with_report_salt(config.report_salt.clone())
+        .with_report_threshold(config.report_threshold);
+
+    reload_lists(&link_service, &config).expect("FATAL: FAILED TO LOAD LINK LISTS");
 
     let state = RealState {
         link_service: Arc::new(link_service),
+        config: Arc::new(config),
+        qr_cache: Arc::new(QrCache::default()),
     };
 
-    let app = Router::new()
-        .route("/links", post(create_link))
-        .route("/l/:code", get(redirect_link))
-        .route("/links/:id/delete", post(delete_link))
-        .with_state(state);
+    reload_on_sighup(state.link_service.clone(), state.config.clone());
+
+    let app = router(state);
 
     let listener = TcpListener::bind("0.0.0.0:8080").await?;
 
     println!("Server running on http://0.0.0.0:8080");
 
-    axum::serve(listener, app).await?;
+    // Routing rules fall back to the peer address for country lookups.
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
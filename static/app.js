@@ -0,0 +1,95 @@
+// Progressive enhancement for the home page. Without scripts the form posts
+// normally and the server renders the same result section.
+(function () {
+  "use strict";
+
+  function bindCopyButtons(root) {
+    if (!navigator.clipboard) {
+      return;
+    }
+
+    root.querySelectorAll("[data-copy]").forEach(function (button) {
+      var field = document.getElementById(button.dataset.copy);
+      if (!field) {
+        return;
+      }
+
+      button.hidden = false;
+      button.addEventListener("click", function () {
+        navigator.clipboard.writeText(field.value).then(function () {
+          button.textContent = "Copied";
+        });
+      });
+    });
+  }
+
+  function showResult(fresh) {
+    var current = document.getElementById("result");
+    if (current) {
+      current.replaceWith(fresh);
+      bindCopyButtons(fresh);
+    }
+  }
+
+  function showError(message) {
+    var result = document.createElement("div");
+    result.id = "result";
+    result.setAttribute("aria-live", "polite");
+
+    var card = document.createElement("div");
+    card.className = "card warning";
+    var text = document.createElement("p");
+    text.textContent = message;
+
+    card.appendChild(text);
+    result.appendChild(card);
+    showResult(result);
+  }
+
+  function enhanceForm(form) {
+    form.addEventListener("submit", function (event) {
+      event.preventDefault();
+
+      var button = form.querySelector("button[type=submit]");
+      button.disabled = true;
+
+      fetch(form.action, {
+        method: "POST",
+        body: new URLSearchParams(new FormData(form)),
+        credentials: "same-origin",
+        headers: { Accept: "text/html" },
+      })
+        .then(function (response) {
+          return response.text().then(function (html) {
+            var page = new DOMParser().parseFromString(html, "text/html");
+            var fresh = page.getElementById("result");
+
+            // Validation errors come back as the home page with the
+            // message in its result section; anything else is unexpected.
+            if (fresh) {
+              showResult(fresh);
+            } else {
+              showError("The link could not be created (" + response.status + "). Please try again.");
+            }
+          });
+        })
+        .catch(function () {
+          // The request may have reached the server, so it is not sent
+          // again: a retry could create the link twice.
+          showError("The server could not be reached. Check your connection and try again.");
+        })
+        .finally(function () {
+          button.disabled = false;
+        });
+    });
+  }
+
+  document.addEventListener("DOMContentLoaded", function () {
+    var form = document.getElementById("shorten");
+    if (form && window.fetch && window.DOMParser) {
+      enhanceForm(form);
+    }
+
+    bindCopyButtons(document);
+  });
+})();